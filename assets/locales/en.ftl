@@ -0,0 +1,112 @@
+# English messages, in the subset of the Fluent syntax described in
+# `src/locale.rs`
+
+language-name = English
+
+## Menu
+
+menu-title = Maze
+menu-width = Width
+menu-height = Height
+menu-rooms = Rooms
+menu-bias = Type
+menu-theme = Theme
+menu-controls = Controls
+menu-ghost-on = Ghost: on
+menu-ghost-off = Ghost: off
+menu-players = Players
+menu-generate = Generate
+menu-close = Quit
+menu-settings = Settings
+menu-presets = Preset
+
+## Controls panel
+
+controls-title = Controls
+controls-deadzone = Deadzone ({ $gamepad })
+controls-reset = Defaults
+
+## Actions
+
+action-up = Up
+action-down = Down
+action-left = Left
+action-right = Right
+action-sprint = Sprint
+action-dash = Dash
+action-menu = Menu
+action-overview = Map
+action-hint = Hint
+action-fog = Fog
+action-zoom = Zoom
+action-record = Record
+action-replay = Replay
+
+## Indicators
+
+loading = Loading...
+replay-recording = Recording
+replay-playing = Replay
+run-time = Time { $time }s
+run-time-best = Time { $time }s / Best { $best }s
+race-winner = Player { $player } wins!
+maze-error = Error: { $error }
+
+## Validation of the maze parameters
+
+invalid-number = not a number
+invalid-size = must be between { $min } and { $max }
+invalid-rooms = at most { $max } rooms fit
+params-info = Up to { $rooms } rooms, generated in ~{ $time } ms
+
+## Settings panel
+
+settings-title = Settings
+settings-display = Window
+settings-display-windowed = Windowed
+settings-display-borderless = Borderless
+settings-display-fullscreen = Fullscreen
+settings-antialiasing = Anti-aliasing
+settings-msaa-2 = MSAA 2x
+settings-msaa-4 = MSAA 4x
+settings-msaa-8 = MSAA 8x
+settings-vsync = V-Sync
+settings-on = On
+settings-off = Off
+settings-lights = Shadows
+settings-quality-low = Off
+settings-quality-medium = Medium
+settings-quality-high = High
+settings-volume = Volume
+settings-language = Language
+settings-maze = First maze
+settings-maze-value = { $width }x{ $height }, { $rooms } rooms
+settings-maze-save = Use the current maze
+
+## Presets panel
+
+presets-title = Presets
+presets-profiles = Profiles
+presets-save = Save
+presets-custom = Custom
+preset-tiny = Tiny
+preset-classic = Classic
+preset-corridors = Corridors
+preset-towers = Towers
+preset-huge = Huge
+
+## Crash dialog
+
+crash-title = The game crashed last time
+crash-info = A crash report was saved. You can copy it and attach it to an issue.
+crash-copy = Copy report
+crash-copied = Copied
+crash-copy-failed = Could not copy
+crash-dismiss = Dismiss
+
+## Win screen
+
+win-title = You found the exit!
+win-new-best = Time { $time }s - a new best!
+win-next = Next maze
+win-menu = Menu
@@ -0,0 +1,111 @@
+# Polskie komunikaty, w podzbiorze składni Fluent opisanym w `src/locale.rs`
+
+language-name = Polski
+
+## Menu
+
+menu-title = Labirynt
+menu-width = Szerokość
+menu-height = Wysokość
+menu-rooms = Pokoje
+menu-bias = Typ
+menu-theme = Motyw
+menu-controls = Sterowanie
+menu-ghost-on = Duch: tak
+menu-ghost-off = Duch: nie
+menu-players = Gracze
+menu-generate = Generuj
+menu-close = Zamknij
+menu-settings = Ustawienia
+menu-presets = Zestaw
+
+## Panel sterowania
+
+controls-title = Sterowanie
+controls-deadzone = Martwa strefa ({ $gamepad })
+controls-reset = Domyślne
+
+## Akcje
+
+action-up = Góra
+action-down = Dół
+action-left = Lewo
+action-right = Prawo
+action-sprint = Bieg
+action-dash = Zryw
+action-menu = Menu
+action-overview = Mapa
+action-hint = Droga
+action-fog = Mgła
+action-zoom = Oddal
+action-record = Nagraj
+action-replay = Powtórka
+
+## Wskaźniki
+
+loading = Ładowanie...
+replay-recording = Nagrywanie
+replay-playing = Powtórka
+run-time = Czas { $time }s
+run-time-best = Czas { $time }s / Rekord { $best }s
+race-winner = Gracz { $player } wygrywa!
+maze-error = Błąd: { $error }
+
+## Sprawdzanie parametrów labiryntu
+
+invalid-number = to nie jest liczba
+invalid-size = musi być od { $min } do { $max }
+invalid-rooms = zmieści się najwyżej { $max } pokoi
+params-info = Do { $rooms } pokoi, generowanie ~{ $time } ms
+
+## Panel ustawień
+
+settings-title = Ustawienia
+settings-display = Okno
+settings-display-windowed = W oknie
+settings-display-borderless = Bez ramki
+settings-display-fullscreen = Pełny ekran
+settings-antialiasing = Wygładzanie
+settings-msaa-2 = MSAA 2x
+settings-msaa-4 = MSAA 4x
+settings-msaa-8 = MSAA 8x
+settings-vsync = Synchronizacja pionowa
+settings-on = Wł.
+settings-off = Wył.
+settings-lights = Cienie
+settings-quality-low = Brak
+settings-quality-medium = Średnie
+settings-quality-high = Wysokie
+settings-volume = Głośność
+settings-language = Język
+settings-maze = Pierwszy labirynt
+settings-maze-value = { $width }x{ $height }, pokoje: { $rooms }
+settings-maze-save = Użyj obecnego labiryntu
+
+## Panel zestawów
+
+presets-title = Zestawy
+presets-profiles = Profile
+presets-save = Zapisz
+presets-custom = Własny
+preset-tiny = Malutki
+preset-classic = Klasyczny
+preset-corridors = Korytarze
+preset-towers = Wieże
+preset-huge = Ogromny
+
+## Okno awarii
+
+crash-title = Gra uległa awarii przy ostatnim uruchomieniu
+crash-info = Zapisano raport o błędzie. Możesz go skopiować i dołączyć do zgłoszenia.
+crash-copy = Kopiuj raport
+crash-copied = Skopiowano
+crash-copy-failed = Nie udało się skopiować
+crash-dismiss = Zamknij
+
+## Ekran wygranej
+
+win-title = Wyjście znalezione!
+win-new-best = Czas { $time }s - nowy rekord!
+win-next = Następny labirynt
+win-menu = Menu
@@ -1,16 +1,19 @@
 //! Algorithms and data structures used for generating and solving the maze.
 
+use std::{collections::VecDeque, iter};
+
 #[cfg(feature = "debug")]
 use bevy::log::debug;
 use bevy::{
 	ecs::system::Resource,
 	log::debug_span,
 	math::UVec2,
-	utils::{HashMap, HashSet},
+	utils::{Entry, HashMap, HashSet},
 };
+use serde::{Deserialize, Serialize};
 use turborand::TurboRand;
 
-use super::maze::{Maze, TilePos, MAZE_SIZE};
+use super::maze::{Maze, MazeError, TilePos, MAX_MAZE_SIZE, MAZE_SIZE, MIN_MAZE_SIZE};
 use crate::{
 	maze::{
 		Direction::{self, Bottom, Left, Right, Top},
@@ -20,7 +23,7 @@ use crate::{
 };
 
 /// Maze generation parameters
-#[derive(Debug, Copy, Clone, Resource)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Resource, Serialize, Deserialize)]
 pub struct MazeParams {
 	/// The width of the maze in tiles
 	pub width: u16,
@@ -54,6 +57,22 @@ impl MazeParams {
 	pub fn height(self) -> u32 {
 		u32::from(self.height)
 	}
+
+	/// Get the largest number of rooms which fit in the maze, one on every tile
+	/// not next to the maze's edge
+	pub const fn max_rooms(self) -> u16 {
+		self.width
+			.saturating_sub(2)
+			.saturating_mul(self.height.saturating_sub(2))
+	}
+
+	/// Whether a maze can be generated with these parameters
+	pub fn is_valid(self) -> bool {
+		let sizes = MIN_MAZE_SIZE..=MAX_MAZE_SIZE;
+		sizes.contains(&self.width)
+			&& sizes.contains(&self.height)
+			&& self.rooms <= self.max_rooms()
+	}
 }
 
 impl Default for MazeParams {
@@ -68,7 +87,7 @@ impl Default for MazeParams {
 }
 
 /// The directional bias of passages in the maze
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DirectionalBias {
 	/// No bias, all directions are equally likely
 	None,
@@ -222,13 +241,15 @@ pub fn gen_rooms(maze: &mut [Tile], rng: &Rand, params: MazeParams) {
 	}
 
 	// Generate `params.rooms - 1` randomly positioned rooms and one room at the
-	// starting position
+	// starting position, which is not sampled so that no position is used twice
 	for pos in rng
 		.sample_multiple_iter(
-			(params.margin_x() + 1..params.margin_x() + params.width() - 1).flat_map(|x| {
-				(params.margin_y() + 1..params.margin_y() + params.height() - 1)
-					.map(move |y| UVec2 { x, y })
-			}),
+			(params.margin_x() + 1..params.margin_x() + params.width() - 1)
+				.flat_map(|x| {
+					(params.margin_y() + 1..params.margin_y() + params.height() - 1)
+						.map(move |y| UVec2 { x, y })
+				})
+				.filter(|&pos| pos != MAZE_SIZE / 2),
 			(params.rooms - 1).into(),
 		)
 		.into_iter()
@@ -295,6 +316,11 @@ impl<T> SortedTree<T> {
 		self.inner.parent(idx)
 	}
 
+	/// Get the index of the root node
+	pub fn root(&self) -> Option<usize> {
+		(0..self.inner.nodes.len()).find(|&i| self.parent(i).is_none())
+	}
+
 	/// Search for the index of a node with the given value (if there are
 	/// multiple nodes with the same value, an arbitrary one is returned)
 	#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
@@ -365,10 +391,23 @@ fn reachable_neighbours(
 		.filter(move |&p| p != pos)
 }
 
+/// Get the distance of `pos` from the start in `distances`, where tiles outside
+/// of the maze (reachable through open sides on its edges) are never reached
+fn distance(distances: &HashMap<TilePos, u32>, pos: TilePos) -> u32 {
+	distances.get(&pos).copied().unwrap_or(u32::MAX)
+}
+
 /// Solve the given maze, returning a minimum-distance tree with `start` as the
 /// root node
+///
+/// # Errors
+/// Returns [`MazeError::OutsideMaze`] if `start` is not part of the maze
 #[cfg_attr(feature = "debug", tracing::instrument(skip(maze)))]
-pub fn solve_maze(maze: &Maze, start: TilePos, params: MazeParams) -> SortedTree<TilePos> {
+pub fn solve_maze(
+	maze: &Maze,
+	start: TilePos,
+	params: MazeParams,
+) -> Result<SortedTree<TilePos>, MazeError> {
 	let mut tree = Tree::new(start);
 
 	// Mark all nodes as unvisited
@@ -378,6 +417,10 @@ pub fn solve_maze(maze: &Maze, start: TilePos, params: MazeParams) -> SortedTree
 		})
 		.collect::<HashSet<_>>();
 
+	if !unvisited.contains(&start) {
+		return Err(MazeError::OutsideMaze(start));
+	}
+
 	// Assign to every node a distance from the start, initially infinity
 	// (`u32::MAX`)
 	let mut distances = unvisited
@@ -386,7 +429,7 @@ pub fn solve_maze(maze: &Maze, start: TilePos, params: MazeParams) -> SortedTree
 		.collect::<HashMap<_, _>>();
 
 	// The start node has a distance to start of 0
-	*distances.get_mut(&start).unwrap() = 0;
+	distances.insert(start, 0);
 	let mut current = start;
 
 	loop {
@@ -399,9 +442,11 @@ pub fn solve_maze(maze: &Maze, start: TilePos, params: MazeParams) -> SortedTree
 		for unvisited_neighbour in reachable_neighbours(maze.get(current), current, params)
 			.filter(|&p| unvisited.contains(&p))
 		{
-			let current_distance = *distances.get(&current).unwrap();
-			let neighbour_distance = distances.get_mut(&unvisited_neighbour).unwrap();
-			*neighbour_distance = (*neighbour_distance).min(current_distance + 1);
+			let current_distance = distance(&distances, current);
+
+			if let Some(neighbour_distance) = distances.get_mut(&unvisited_neighbour) {
+				*neighbour_distance = (*neighbour_distance).min(current_distance + 1);
+			}
 		}
 
 		drop(update_neighbours);
@@ -415,8 +460,8 @@ pub fn solve_maze(maze: &Maze, start: TilePos, params: MazeParams) -> SortedTree
 
 		// Append the current node to its neighbour with the minimum distance
 		let min_neighbour = reachable_neighbours(maze.get(current), current, params)
-			.min_by_key(|n| *distances.get(n).unwrap())
-			.filter(|n| *distances.get(n).unwrap() != u32::MAX)
+			.min_by_key(|n| distance(&distances, *n))
+			.filter(|n| distance(&distances, *n) != u32::MAX)
 			.unwrap_or(start);
 		tree.append(current, tree.search(&min_neighbour).unwrap_or_default());
 
@@ -426,8 +471,8 @@ pub fn solve_maze(maze: &Maze, start: TilePos, params: MazeParams) -> SortedTree
 		// Go to the unvisited node with the smallest finite current distance
 		current = if let Some(new) = unvisited
 			.iter()
-			.min_by_key(|&n| *distances.get(n).unwrap())
-			.filter(|&n| *distances.get(n).unwrap() != u32::MAX)
+			.min_by_key(|&n| distance(&distances, *n))
+			.filter(|&n| distance(&distances, *n) != u32::MAX)
 		{
 			*new
 		} else {
@@ -438,5 +483,232 @@ pub fn solve_maze(maze: &Maze, start: TilePos, params: MazeParams) -> SortedTree
 		drop(go_next_node);
 	}
 
-	SortedTree::new(tree)
+	Ok(SortedTree::new(tree))
+}
+
+/// Find the shortest route through the maze from `from` to `to` using a
+/// breadth-first search, returning the tiles along the route (excluding `from`
+/// and including `to`), or `None` if either tile is outside of the maze or `to`
+/// is unreachable
+#[cfg_attr(feature = "debug", tracing::instrument(skip(maze)))]
+pub fn find_route(
+	maze: &Maze,
+	from: TilePos,
+	to: TilePos,
+	params: MazeParams,
+) -> Option<Vec<TilePos>> {
+	let in_maze = |TilePos { x, y }: TilePos| {
+		(params.margin_x()..params.margin_x() + params.width()).contains(&x)
+			&& (params.margin_y()..params.margin_y() + params.height()).contains(&y)
+	};
+
+	if !in_maze(from) || !in_maze(to) {
+		return None;
+	}
+
+	let mut previous = HashMap::new();
+	previous.insert(from, from);
+	let mut queue = VecDeque::from([from]);
+
+	while let Some(current) = queue.pop_front() {
+		if current == to {
+			let mut route = iter::successors(Some(to), |p| Some(previous[p]))
+				.take_while(|&p| p != from)
+				.collect::<Vec<_>>();
+			route.reverse();
+			return Some(route);
+		}
+
+		for next in reachable_neighbours(maze.get(current), current, params) {
+			if let Entry::Vacant(entry) = previous.entry(next) {
+				entry.insert(current);
+				queue.push_back(next);
+			}
+		}
+	}
+
+	None
+}
+
+/// Get all tiles visible from `origin` within `radius` tiles, using recursive
+/// shadowcasting
+///
+/// Since walls in the maze are between tiles rather than being tiles
+/// themselves, shadowcasting is done on a grid with twice the resolution of
+/// the maze, where every tile is surrounded by cells for its walls and corners
+#[cfg_attr(feature = "debug", tracing::instrument(skip(maze)))]
+pub fn line_of_sight(maze: &Maze, origin: TilePos, radius: u32) -> Vec<TilePos> {
+	/// Octant transformation multipliers (`xx`, `xy`, `yx`, `yy`)
+	const OCTANTS: [[i32; 4]; 8] = [
+		[1, 0, 0, 1],
+		[0, 1, 1, 0],
+		[0, -1, 1, 0],
+		[-1, 0, 0, 1],
+		[-1, 0, 0, -1],
+		[0, -1, -1, 0],
+		[0, 1, -1, 0],
+		[1, 0, 0, -1],
+	];
+
+	let params = maze.params;
+	let (w, h) = (params.width(), params.height());
+
+	if !(params.margin_x()..params.margin_x() + w).contains(&origin.x)
+		|| !(params.margin_y()..params.margin_y() + h).contains(&origin.y)
+	{
+		return vec![origin];
+	}
+
+	let grid = LosGrid {
+		maze,
+		params,
+		size: (2 * w + 1, 2 * h + 1),
+	};
+
+	let (ox, oy) = (
+		i32::try_from(2 * (origin.x - params.margin_x()) + 1).unwrap(),
+		i32::try_from(2 * (origin.y - params.margin_y()) + 1).unwrap(),
+	);
+	let radius = i32::try_from(2 * radius).unwrap();
+
+	let mut visible = vec![origin];
+
+	for [xx, xy, yx, yy] in OCTANTS {
+		grid.cast_light(
+			(ox, oy),
+			1,
+			1.0,
+			0.0,
+			radius,
+			[xx, xy, yx, yy],
+			&mut |x, y| {
+				// Only tile centers (odd coordinates) correspond to tiles
+				if x % 2 == 1 && y % 2 == 1 {
+					visible.push(TilePos {
+						x: params.margin_x() + u32::try_from(x / 2).unwrap(),
+						y: params.margin_y() + u32::try_from(y / 2).unwrap(),
+					});
+				}
+			},
+		);
+	}
+
+	visible.sort_unstable();
+	visible.dedup();
+	visible
+}
+
+/// The double-resolution grid used by [`line_of_sight`]
+struct LosGrid<'a> {
+	maze: &'a Maze,
+	params: MazeParams,
+	size: (u32, u32),
+}
+
+impl LosGrid<'_> {
+	/// Get the tile at the given (in-bounds) tile coordinates relative to the
+	/// maze's margin
+	fn tile(&self, x: u32, y: u32) -> Tile {
+		self.maze.get(TilePos {
+			x: x + self.params.margin_x(),
+			y: y + self.params.margin_y(),
+		})
+	}
+
+	/// Whether the cell at `(x, y)` blocks sight
+	fn is_opaque(&self, x: i32, y: i32) -> bool {
+		let (Ok(ux), Ok(uy)) = (u32::try_from(x), u32::try_from(y)) else {
+			return true;
+		};
+
+		if ux >= self.size.0 || uy >= self.size.1 {
+			return true;
+		}
+
+		match (ux % 2, uy % 2) {
+			// Tile center
+			(1, 1) => false,
+			// Horizontal wall
+			(1, 0) if uy / 2 < self.params.height() => self.tile(ux / 2, uy / 2).is_closed(Bottom),
+			(1, 0) => self.tile(ux / 2, uy / 2 - 1).is_closed(Top),
+			// Vertical wall
+			(0, 1) if ux / 2 < self.params.width() => self.tile(ux / 2, uy / 2).is_closed(Left),
+			(0, 1) => self.tile(ux / 2 - 1, uy / 2).is_closed(Right),
+			// Corner, blocking if any wall touching it does
+			_ => {
+				self.is_opaque(x - 1, y)
+					|| self.is_opaque(x + 1, y)
+					|| self.is_opaque(x, y - 1)
+					|| self.is_opaque(x, y + 1)
+			}
+		}
+	}
+
+	/// Scan one octant, calling `visit` on every visible cell
+	#[allow(clippy::too_many_arguments, clippy::cast_precision_loss)]
+	fn cast_light(
+		&self,
+		(ox, oy): (i32, i32),
+		row: i32,
+		mut start: f32,
+		end: f32,
+		radius: i32,
+		[xx, xy, yx, yy]: [i32; 4],
+		visit: &mut impl FnMut(i32, i32),
+	) {
+		if start < end {
+			return;
+		}
+
+		let mut new_start = start;
+
+		for j in row..=radius {
+			let dy = -j;
+			let mut blocked = false;
+
+			for dx in -j..=0 {
+				let x = ox + dx * xx + dy * xy;
+				let y = oy + dx * yx + dy * yy;
+				let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+				let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+
+				if start < right_slope {
+					continue;
+				}
+
+				if end > left_slope {
+					break;
+				}
+
+				if dx * dx + dy * dy <= radius * radius && !self.is_opaque(x, y) {
+					visit(x, y);
+				}
+
+				if blocked {
+					if self.is_opaque(x, y) {
+						new_start = right_slope;
+					} else {
+						blocked = false;
+						start = new_start;
+					}
+				} else if self.is_opaque(x, y) && j < radius {
+					blocked = true;
+					self.cast_light(
+						(ox, oy),
+						j + 1,
+						start,
+						left_slope,
+						radius,
+						[xx, xy, yx, yy],
+						visit,
+					);
+					new_start = right_slope;
+				}
+			}
+
+			if blocked {
+				break;
+			}
+		}
+	}
 }
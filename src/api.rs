@@ -0,0 +1,187 @@
+//! The JavaScript control API of the wasm build, which lets the page embedding
+//! the game control it.
+//!
+//! Commands are sent with the exported functions (e.g. `setParams({ width: 9,
+//! height: 7, rooms: 2, bias: "None" })` or `openMenu()`), or by dispatching a
+//! `maze-command` `CustomEvent` on `window` with the command as its `detail`
+//! (e.g. `{ command: "regenerate", seed: 42 }`, see [`Command`]). The current
+//! maze is returned as JSON by `getMaze()`.
+
+use std::sync::{Mutex, PoisonError};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{CustomEvent, Event};
+
+use crate::{
+	algorithms::MazeParams,
+	commands::Command,
+	maze::{Direction, Maze, MazeRegenerated, TilePos},
+};
+
+/// The type of the `CustomEvent`s commands can be dispatched as
+const EVENT_NAME: &str = "maze-command";
+
+/// The current maze as JSON, once the first one is generated
+static CURRENT_MAZE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Convert a JS value to `T` through JSON
+fn from_js<T: DeserializeOwned>(value: &JsValue) -> Result<T, String> {
+	let json = js_sys::JSON::stringify(value)
+		.map_err(|_| "the value can not be converted to JSON".to_string())?;
+
+	serde_json::from_str(&String::from(json)).map_err(|e| e.to_string())
+}
+
+/// The current maze, as returned by `getMaze()`
+#[derive(Debug, Clone, Serialize)]
+struct MazeInfo {
+	/// The seed the maze was generated with, as a string since JS numbers can
+	/// not represent all seeds
+	seed: String,
+	params: MazeParams,
+	/// The position of the exit, counted in tiles right and down from the
+	/// maze's top left tile
+	exit: [u32; 2],
+	solution_length: u32,
+	dead_ends: u32,
+	/// The maze's tiles in rows from the top, each with a bit set for every
+	/// closed side (8 for the top, 4 for the right, 2 for the bottom and 1 for
+	/// the left side)
+	walls: Vec<Vec<u8>>,
+}
+
+impl MazeInfo {
+	fn new(event: &MazeRegenerated, maze: &Maze) -> Self {
+		let params = event.params;
+		let (left, bottom) = (params.margin_x(), params.margin_y());
+		let top = bottom + params.height() - 1;
+
+		// The y axis of tile positions points up
+		let walls = (bottom..=top)
+			.rev()
+			.map(|y| {
+				(left..left + params.width())
+					.map(|x| {
+						let tile = maze.get(TilePos { x, y });
+
+						[
+							Direction::Top,
+							Direction::Right,
+							Direction::Bottom,
+							Direction::Left,
+						]
+						.into_iter()
+						.fold(0, |bits, side| (bits << 1) | u8::from(tile.is_closed(side)))
+					})
+					.collect()
+			})
+			.collect();
+
+		Self {
+			seed: event.seed.to_string(),
+			params,
+			exit: [event.exit.x - left, top - event.exit.y],
+			solution_length: event.stats.solution_length,
+			dead_ends: event.stats.dead_ends,
+			walls,
+		}
+	}
+}
+
+/// Send a command, given as an object like the `detail` of a `maze-command`
+/// event
+///
+/// # Errors
+/// Throws if the command or its maze parameters are invalid
+#[wasm_bindgen(js_name = sendCommand)]
+pub fn send_command(command: &JsValue) -> Result<(), JsError> {
+	from_js(command)
+		.and_then(Command::validate)
+		.map(Command::send)
+		.map_err(|e| JsError::new(&e))
+}
+
+/// Set the parameters of the next generated maze, given as an object with the
+/// `width`, `height`, `rooms` and `bias` properties
+///
+/// # Errors
+/// Throws if the parameters are invalid
+#[wasm_bindgen(js_name = setParams)]
+pub fn set_params(params: &JsValue) -> Result<(), JsError> {
+	from_js(params)
+		.map(Command::SetParams)
+		.and_then(Command::validate)
+		.map(Command::send)
+		.map_err(|e| JsError::new(&e))
+}
+
+/// Generate a new maze with the current parameters and the given seed (a
+/// `BigInt`), or a random seed if there is none
+#[wasm_bindgen]
+pub fn regenerate(seed: Option<u64>) {
+	Command::Regenerate { seed }.send();
+}
+
+/// Open the menu, pausing the game
+#[wasm_bindgen(js_name = openMenu)]
+pub fn open_menu() {
+	Command::OpenMenu.send();
+}
+
+/// Close the menu, resuming the game
+#[wasm_bindgen(js_name = closeMenu)]
+pub fn close_menu() {
+	Command::CloseMenu.send();
+}
+
+/// Get the current maze as JSON, or `undefined` before the first maze is
+/// generated
+#[wasm_bindgen(js_name = getMaze)]
+pub fn get_maze() -> Option<String> {
+	CURRENT_MAZE
+		.lock()
+		.unwrap_or_else(PoisonError::into_inner)
+		.clone()
+}
+
+/// Listen for `maze-command` events
+///
+/// # Panics
+/// This function panics if the `window` JS object or its `addEventListener`
+/// method are unavailable or throw
+pub fn initialize() {
+	let listener = Closure::<dyn Fn(Event)>::new(|event: Event| {
+		let Some(event) = event.dyn_ref::<CustomEvent>() else {
+			warn!("`{EVENT_NAME}` event is not a `CustomEvent`");
+			return;
+		};
+
+		match from_js(&event.detail()).and_then(Command::validate) {
+			Ok(command) => command.send(),
+			Err(e) => warn!("invalid `{EVENT_NAME}` event: {e}"),
+		}
+	});
+
+	web_sys::window()
+		.expect("JS `window` not available")
+		.add_event_listener_with_callback(EVENT_NAME, listener.as_ref().unchecked_ref())
+		.expect("JS `addEventListener` failed");
+
+	// The listener is used until the page is closed
+	listener.forget();
+}
+
+/// Update the maze returned by `getMaze()` when a new one is generated
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn snapshot(mut regenerated: EventReader<MazeRegenerated>, maze: Res<Maze>) {
+	let Some(event) = regenerated.read().last() else {
+		return;
+	};
+
+	match serde_json::to_string(&MazeInfo::new(event, &maze)) {
+		Ok(json) => *CURRENT_MAZE.lock().unwrap_or_else(PoisonError::into_inner) = Some(json),
+		Err(e) => warn!("could not serialize the maze: {e}"),
+	}
+}
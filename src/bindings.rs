@@ -0,0 +1,533 @@
+//! Rebindable input: a mapping of keys and gamepad buttons/axes to actions,
+//! saved with [`storage`](crate::storage) whenever it changes.
+
+use std::{
+	collections::BTreeMap,
+	fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+/// The name of the file the [`InputMap`] is saved in
+const FILE_NAME: &str = "bindings.ron";
+/// The deadzone of gamepads without a configured deadzone
+pub const DEFAULT_DEADZONE: f32 = 0.05;
+/// The largest allowed deadzone
+pub const MAX_DEADZONE: f32 = 0.9;
+/// How far a gamepad axis has to be moved to be bound while rebinding
+const CAPTURE_THRESHOLD: f32 = 0.5;
+/// The gamepad axes which can be bound to movement actions
+const AXES: [GamepadAxisType; 4] = [
+	GamepadAxisType::LeftStickX,
+	GamepadAxisType::LeftStickY,
+	GamepadAxisType::RightStickX,
+	GamepadAxisType::RightStickY,
+];
+
+/// Something the player can do using a key or gamepad input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+	Up,
+	Down,
+	Left,
+	Right,
+	Sprint,
+	Dash,
+	Menu,
+	/// Show or hide the minimap
+	Overview,
+	/// Show or hide the solution on the minimap
+	Hint,
+	Fog,
+	Zoom,
+	/// Start or stop recording a replay
+	Record,
+	/// Start or stop playing back the saved replay
+	Replay,
+}
+
+impl Action {
+	pub const ALL: [Self; 13] = [
+		Self::Up,
+		Self::Down,
+		Self::Left,
+		Self::Right,
+		Self::Sprint,
+		Self::Dash,
+		Self::Menu,
+		Self::Overview,
+		Self::Hint,
+		Self::Fog,
+		Self::Zoom,
+		Self::Record,
+		Self::Replay,
+	];
+
+	/// The id of the action's message
+	pub const fn message(self) -> &'static str {
+		match self {
+			Self::Up => "action-up",
+			Self::Down => "action-down",
+			Self::Left => "action-left",
+			Self::Right => "action-right",
+			Self::Sprint => "action-sprint",
+			Self::Dash => "action-dash",
+			Self::Menu => "action-menu",
+			Self::Overview => "action-overview",
+			Self::Hint => "action-hint",
+			Self::Fog => "action-fog",
+			Self::Zoom => "action-zoom",
+			Self::Record => "action-record",
+			Self::Replay => "action-replay",
+		}
+	}
+
+	/// Whether this is one of the movement actions, which can be bound to
+	/// gamepad axes
+	pub const fn is_movement(self) -> bool {
+		matches!(self, Self::Up | Self::Down | Self::Left | Self::Right)
+	}
+}
+
+/// The direction in which a gamepad axis has to be moved to trigger a
+/// [`Binding`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AxisDirection {
+	Positive,
+	Negative,
+}
+
+/// A half of the keyboard, for two players sharing a keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardHalf {
+	Left,
+	Right,
+}
+
+impl KeyboardHalf {
+	/// Whether `key` is on this half of the keyboard
+	pub const fn contains(self, key: KeyCode) -> bool {
+		use KeyCode::{
+			AltLeft, Backquote, CapsLock, ControlLeft, Digit1, Digit2, Digit3, Digit4, Digit5,
+			Escape, IntlBackslash, KeyA, KeyB, KeyC, KeyD, KeyE, KeyF, KeyG, KeyQ, KeyR, KeyS,
+			KeyT, KeyV, KeyW, KeyX, KeyZ, ShiftLeft, Space, SuperLeft, Tab, F1, F2, F3, F4, F5, F6,
+		};
+
+		let is_left =
+			matches!(
+				key,
+				Escape
+					| F1 | F2 | F3 | F4
+					| F5 | F6 | Backquote
+					| Digit1 | Digit2
+					| Digit3 | Digit4
+					| Digit5 | Tab | KeyQ
+					| KeyW | KeyE | KeyR
+					| KeyT | CapsLock
+					| KeyA | KeyS | KeyD
+					| KeyF | KeyG | ShiftLeft
+					| IntlBackslash | KeyZ
+					| KeyX | KeyC | KeyV
+					| KeyB | ControlLeft
+					| SuperLeft | AltLeft
+					| Space
+			);
+
+		match self {
+			Self::Left => is_left,
+			Self::Right => !is_left,
+		}
+	}
+}
+
+/// The input device controlling one of the players in local multiplayer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+	/// The bindings to keys on one half of the keyboard
+	Keyboard(KeyboardHalf),
+	/// The bindings to buttons and axes of one gamepad
+	Gamepad(Gamepad),
+}
+
+/// An input which triggers an [`Action`]
+///
+/// Gamepad inputs are not tied to a specific gamepad, so they are triggered by
+/// any connected gamepad (except for a player's [`Device`] in local
+/// multiplayer)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+	Key(KeyCode),
+	Button(GamepadButtonType),
+	Axis(GamepadAxisType, AxisDirection),
+}
+
+impl Display for Binding {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		match self {
+			Self::Key(key) => {
+				let name = format!("{key:?}");
+				f.write_str(name.strip_prefix("Key").unwrap_or(&name))
+			}
+			Self::Button(button) => f.write_fmt(format_args!("Pad {button:?}")),
+			Self::Axis(axis, AxisDirection::Positive) => f.write_fmt(format_args!("Pad {axis:?}+")),
+			Self::Axis(axis, AxisDirection::Negative) => f.write_fmt(format_args!("Pad {axis:?}-")),
+		}
+	}
+}
+
+/// The bindings of all actions and the deadzones of gamepads
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct InputMap {
+	bindings: BTreeMap<Action, Vec<Binding>>,
+	/// Deadzones of gamepad axes by gamepad name, since gamepad ids are not
+	/// kept between runs
+	deadzones: BTreeMap<String, f32>,
+}
+
+impl Default for InputMap {
+	fn default() -> Self {
+		use Binding::{Axis, Button, Key};
+		use GamepadAxisType::{LeftStickX, LeftStickY};
+
+		let bindings = [
+			(Action::Up, vec![
+				Key(KeyCode::KeyW),
+				Key(KeyCode::ArrowUp),
+				Button(GamepadButtonType::DPadUp),
+				Axis(LeftStickY, AxisDirection::Positive),
+			]),
+			(Action::Down, vec![
+				Key(KeyCode::KeyS),
+				Key(KeyCode::ArrowDown),
+				Button(GamepadButtonType::DPadDown),
+				Axis(LeftStickY, AxisDirection::Negative),
+			]),
+			(Action::Left, vec![
+				Key(KeyCode::KeyA),
+				Key(KeyCode::ArrowLeft),
+				Button(GamepadButtonType::DPadLeft),
+				Axis(LeftStickX, AxisDirection::Negative),
+			]),
+			(Action::Right, vec![
+				Key(KeyCode::KeyD),
+				Key(KeyCode::ArrowRight),
+				Button(GamepadButtonType::DPadRight),
+				Axis(LeftStickX, AxisDirection::Positive),
+			]),
+			(Action::Sprint, vec![
+				Key(KeyCode::ShiftLeft),
+				Key(KeyCode::ShiftRight),
+				Button(GamepadButtonType::RightTrigger),
+			]),
+			(Action::Dash, vec![
+				Key(KeyCode::Space),
+				Key(KeyCode::ControlRight),
+				Button(GamepadButtonType::South),
+			]),
+			(Action::Menu, vec![
+				Key(KeyCode::Escape),
+				Button(GamepadButtonType::Start),
+			]),
+			(Action::Overview, vec![
+				Key(KeyCode::KeyM),
+				Button(GamepadButtonType::Select),
+			]),
+			(Action::Hint, vec![Key(KeyCode::KeyH)]),
+			(Action::Fog, vec![Key(KeyCode::KeyF)]),
+			(Action::Zoom, vec![
+				Key(KeyCode::KeyZ),
+				Button(GamepadButtonType::North),
+			]),
+			(Action::Record, vec![Key(KeyCode::F5)]),
+			(Action::Replay, vec![Key(KeyCode::F6)]),
+		];
+
+		Self {
+			bindings: bindings.into_iter().collect(),
+			deadzones: BTreeMap::new(),
+		}
+	}
+}
+
+impl InputMap {
+	/// Get all bindings of `action`
+	pub fn bindings(&self, action: Action) -> &[Binding] {
+		self.bindings.get(&action).map_or(&[], Vec::as_slice)
+	}
+
+	/// Add a binding to `action`, unless it is already bound to it
+	pub fn add(&mut self, action: Action, binding: Binding) {
+		let bindings = self.bindings.entry(action).or_default();
+
+		if !bindings.contains(&binding) {
+			bindings.push(binding);
+		}
+	}
+
+	/// Remove all bindings of `action`
+	pub fn clear(&mut self, action: Action) {
+		self.bindings.insert(action, Vec::new());
+	}
+
+	/// Get the deadzone of the gamepad called `name`
+	pub fn deadzone(&self, name: Option<&str>) -> f32 {
+		name.and_then(|name| self.deadzones.get(name))
+			.copied()
+			.unwrap_or(DEFAULT_DEADZONE)
+	}
+
+	/// Set the deadzone of the gamepad called `name`, clamped to the range
+	/// from `0.0` to [`MAX_DEADZONE`]
+	pub fn set_deadzone(&mut self, name: &str, deadzone: f32) {
+		self.deadzones
+			.insert(name.to_string(), deadzone.clamp(0.0, MAX_DEADZONE));
+	}
+
+	/// Load the saved input map, falling back to the defaults for anything
+	/// that was not saved
+	///
+	/// Tab is removed from the menu's bindings, since it is used for moving
+	/// between the menu's elements (but was bound to the menu by default in
+	/// older versions)
+	fn load() -> Self {
+		let Some(saved) = storage::load_config(FILE_NAME) else {
+			return Self::default();
+		};
+
+		let mut map = match ron::from_str::<Self>(&saved) {
+			Ok(map) => map,
+			Err(e) => {
+				warn!("invalid saved bindings, using the defaults: {e}");
+				return Self::default();
+			}
+		};
+
+		for (action, bindings) in Self::default().bindings {
+			map.bindings.entry(action).or_insert(bindings);
+		}
+
+		if let Some(menu) = map.bindings.get_mut(&Action::Menu) {
+			menu.retain(|&binding| binding != Binding::Key(KeyCode::Tab));
+		}
+
+		map
+	}
+}
+
+/// The action currently being rebound, if any
+///
+/// While an action is being rebound, the next pressed key or gamepad button
+/// (or moved gamepad axis for movement actions) is bound to it
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct Rebinding(pub Option<Action>);
+
+/// Access to the state of [`Action`]s through the [`InputMap`]
+#[derive(SystemParam)]
+pub struct Actions<'w> {
+	map: Res<'w, InputMap>,
+	keys: Res<'w, ButtonInput<KeyCode>>,
+	gamepads: Res<'w, Gamepads>,
+	buttons: Res<'w, ButtonInput<GamepadButton>>,
+	axes: Res<'w, Axis<GamepadAxis>>,
+}
+
+impl Actions<'_> {
+	/// Get how far `action` is pressed, within the range from `0.0` to `1.0`
+	///
+	/// This is the largest value of all of the action's bindings, with gamepad
+	/// axes within the gamepad's deadzone ignored
+	pub fn value(&self, action: Action) -> f32 {
+		self.value_on(None, action)
+	}
+
+	/// Whether `action` is pressed
+	pub fn pressed(&self, action: Action) -> bool {
+		self.value(action) > 0.0
+	}
+
+	/// Whether any key or gamepad button bound to `action` was just pressed
+	///
+	/// Gamepad axes are ignored, since they can not be "just pressed"
+	pub fn just_pressed(&self, action: Action) -> bool {
+		self.just_pressed_on(None, action)
+	}
+
+	/// Get how far `action` is pressed using only the bindings of `device`
+	pub fn device_value(&self, device: Device, action: Action) -> f32 {
+		self.value_on(Some(device), action)
+	}
+
+	/// Whether `action` is pressed using only the bindings of `device`
+	pub fn device_pressed(&self, device: Device, action: Action) -> bool {
+		self.device_value(device, action) > 0.0
+	}
+
+	/// Whether `action` was just pressed using only the bindings of `device`
+	pub fn device_just_pressed(&self, device: Device, action: Action) -> bool {
+		self.just_pressed_on(Some(device), action)
+	}
+
+	fn value_on(&self, device: Option<Device>, action: Action) -> f32 {
+		self.map
+			.bindings(action)
+			.iter()
+			.map(|&binding| self.binding_value(device, binding))
+			.fold(0.0, f32::max)
+	}
+
+	fn just_pressed_on(&self, device: Option<Device>, action: Action) -> bool {
+		self.map
+			.bindings(action)
+			.iter()
+			.any(|&binding| match binding {
+				Binding::Key(key) => has_key(device, key) && self.keys.just_pressed(key),
+				Binding::Button(button_type) => self.gamepads(device).any(|gamepad| {
+					self.buttons.just_pressed(GamepadButton {
+						gamepad,
+						button_type,
+					})
+				}),
+				Binding::Axis(..) => false,
+			})
+	}
+
+	/// The connected gamepads belonging to `device` (all of them without one)
+	fn gamepads(&self, device: Option<Device>) -> impl Iterator<Item = Gamepad> + '_ {
+		self.gamepads.iter().filter(move |&gamepad| match device {
+			None => true,
+			Some(Device::Gamepad(device)) => device == gamepad,
+			Some(Device::Keyboard(_)) => false,
+		})
+	}
+
+	fn binding_value(&self, device: Option<Device>, binding: Binding) -> f32 {
+		match binding {
+			Binding::Key(key) => {
+				if has_key(device, key) && self.keys.pressed(key) {
+					1.0
+				} else {
+					0.0
+				}
+			}
+			Binding::Button(button_type) => {
+				let pressed = self.gamepads(device).any(|gamepad| {
+					self.buttons.pressed(GamepadButton {
+						gamepad,
+						button_type,
+					})
+				});
+
+				if pressed {
+					1.0
+				} else {
+					0.0
+				}
+			}
+			Binding::Axis(axis_type, direction) => self
+				.gamepads(device)
+				.filter_map(|gamepad| {
+					let value = self.axes.get(GamepadAxis { gamepad, axis_type })?;
+					let value = match direction {
+						AxisDirection::Positive => value,
+						AxisDirection::Negative => -value,
+					};
+
+					(value > self.map.deadzone(self.gamepads.name(gamepad))).then_some(value)
+				})
+				.fold(0.0, f32::max)
+				.min(1.0),
+		}
+	}
+}
+
+/// Whether `key` belongs to `device` (every key does without one)
+const fn has_key(device: Option<Device>, key: KeyCode) -> bool {
+	match device {
+		None => true,
+		Some(Device::Keyboard(half)) => half.contains(key),
+		Some(Device::Gamepad(_)) => false,
+	}
+}
+
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn initialize(mut commands: Commands) {
+	commands.insert_resource(InputMap::load());
+	commands.insert_resource(Rebinding::default());
+}
+
+/// Bind the next pressed key, gamepad button or gamepad axis to the action
+/// being rebound
+///
+/// The captured key or button is reset, so that it does not also trigger its
+/// current action. This must run before any systems using [`Actions`].
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn capture(
+	mut rebinding: ResMut<Rebinding>,
+	mut map: ResMut<InputMap>,
+	mut keys: ResMut<ButtonInput<KeyCode>>,
+	mut buttons: ResMut<ButtonInput<GamepadButton>>,
+	gamepads: Res<Gamepads>,
+	axes: Res<Axis<GamepadAxis>>,
+) {
+	let Some(action) = rebinding.0 else {
+		return;
+	};
+
+	let key = keys.get_just_pressed().next().copied();
+	let button = buttons.get_just_pressed().next().copied();
+
+	let binding = match (key, button) {
+		(Some(key), _) => {
+			keys.reset(key);
+			Binding::Key(key)
+		}
+		(None, Some(button)) => {
+			buttons.reset(button);
+			Binding::Button(button.button_type)
+		}
+		(None, None) => {
+			if !action.is_movement() {
+				return;
+			}
+
+			let moved = gamepads
+				.iter()
+				.flat_map(|gamepad| AXES.map(|axis_type| GamepadAxis { gamepad, axis_type }))
+				.find_map(|axis| {
+					let value = axes.get(axis)?;
+					(value.abs() > CAPTURE_THRESHOLD).then_some((axis.axis_type, value))
+				});
+
+			let Some((axis_type, value)) = moved else {
+				return;
+			};
+
+			if value > 0.0 {
+				Binding::Axis(axis_type, AxisDirection::Positive)
+			} else {
+				Binding::Axis(axis_type, AxisDirection::Negative)
+			}
+		}
+	};
+
+	map.add(action, binding);
+	rebinding.0 = None;
+}
+
+/// Save the [`InputMap`] whenever it changes
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn save(map: Res<InputMap>) {
+	if !map.is_changed() || map.is_added() {
+		return;
+	}
+
+	match ron::ser::to_string_pretty(&*map, PrettyConfig::default()) {
+		Ok(saved) => storage::save_config(FILE_NAME, &saved),
+		Err(e) => warn!("could not serialize bindings: {e}"),
+	}
+}
@@ -2,11 +2,41 @@ use std::f32;
 
 use bevy::{prelude::*, render::camera::ClearColorConfig, window::PrimaryWindow};
 
-use crate::player::Player;
+use crate::{
+	bindings::{Action, Actions},
+	maze::{tile_position, MazeRegenerated, START},
+	player::Player,
+	theme::{Theme, ThemeChanged},
+};
 
 const SUN_BRIGHTNESS: f32 = 50_000.0;
+/// The scale of the cameras' projections while zoomed out
+const ZOOMED_OUT_SCALE: f32 = 2.0;
+/// The proportion of the width/height of the screen the area between all
+/// players may take up before the cameras zoom out in local multiplayer
+const FIT_PROPORTION: f32 = 0.7;
+
+/// Whether the cameras are zoomed out ([`Action::Zoom`])
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct Zoom {
+	pub zoomed_out: bool,
+}
+
+impl Zoom {
+	/// The scale of the cameras' projections, before zooming out to fit all
+	/// players
+	const fn scale(self) -> f32 {
+		if self.zoomed_out {
+			ZOOMED_OUT_SCALE
+		} else {
+			1.0
+		}
+	}
+}
 
 pub fn initialize(mut commands: Commands) {
+	commands.insert_resource(Zoom::default());
+
 	commands.spawn((
 		Camera2dBundle {
 			camera: Camera {
@@ -54,24 +84,68 @@ pub fn initialize(mut commands: Commands) {
 		});
 }
 
+/// Follow the player, or the area between all players in local multiplayer,
+/// zooming out as far as needed to keep all of them on the screen
+#[allow(clippy::type_complexity)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
 pub fn movement(
 	mut cameras: Query<&mut Transform, (With<Camera>, Without<Player>)>,
-	player: Query<&Transform, With<Player>>,
+	mut projections_2d: Query<&mut OrthographicProjection>,
+	mut projections_3d: Query<&mut Projection>,
+	zoom: Res<Zoom>,
+	players: Query<&Transform, With<Player>>,
 	window: Query<&Window, With<PrimaryWindow>>,
 ) {
 	/// The free movement space on each side of the screen as a proportion of
 	/// the width/height of the screen
 	const FREE_MOVEMENT_SPACE_PROPORTION: f32 = 0.2;
 
-	for mut camera in &mut cameras {
-		let player = player.single();
-		let window = window.single();
+	let Ok(window) = window.get_single() else {
+		return;
+	};
+
+	let mut positions = players.iter().map(|p| p.translation.truncate());
+	let Some(first) = positions.next() else {
+		return;
+	};
 
+	let (min, max, count) = positions.fold((first, first, 1), |(min, max, count), pos| {
+		(min.min(pos), max.max(pos), count + 1)
+	});
+
+	let screen = Vec2::new(window.width(), window.height());
+	let fit = ((max - min) / (screen * FIT_PROPORTION)).max_element();
+	let scale = zoom.scale().max(fit);
+
+	for mut projection in &mut projections_2d {
+		if (projection.scale - scale).abs() > f32::EPSILON {
+			projection.scale = scale;
+		}
+	}
+
+	for mut projection in &mut projections_3d {
+		if let Projection::Orthographic(projection) = &mut *projection {
+			if (projection.scale - scale).abs() > f32::EPSILON {
+				projection.scale = scale;
+			}
+		}
+	}
+
+	// All players are kept in view by zooming, so there's no free movement
+	let free_movement = if count > 1 {
+		0.0
+	} else {
+		FREE_MOVEMENT_SPACE_PROPORTION
+	};
+
+	let target = (min + max) / 2.0;
+
+	for mut camera in &mut cameras {
 		let (width, height) = (
-			window.width() * FREE_MOVEMENT_SPACE_PROPORTION,
-			window.height() * FREE_MOVEMENT_SPACE_PROPORTION,
+			window.width() * scale * free_movement,
+			window.height() * scale * free_movement,
 		);
-		let player_displacement = player.translation - camera.translation;
+		let player_displacement = target.extend(0.0) - camera.translation;
 
 		let deadzoned_displacement_x = player_displacement.x.abs() - width;
 		let deadzoned_displacement_x = if deadzoned_displacement_x.is_sign_negative() {
@@ -96,3 +170,47 @@ pub fn movement(
 		};
 	}
 }
+
+/// Zoom the cameras out or back in ([`Action::Zoom`])
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn zoom(actions: Actions, mut zoom: ResMut<Zoom>) {
+	if actions.just_pressed(Action::Zoom) {
+		zoom.zoomed_out = !zoom.zoomed_out;
+	}
+}
+
+/// Center the cameras on the start when the maze is regenerated
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn respawn(
+	mut events: EventReader<MazeRegenerated>,
+	mut cameras: Query<&mut Transform, (With<Camera>, Without<Player>)>,
+) {
+	if events.is_empty() {
+		return;
+	}
+
+	events.clear();
+
+	let start = tile_position(START.index());
+
+	for mut camera in &mut cameras {
+		camera.translation.x = start.x;
+		camera.translation.y = start.y;
+	}
+}
+
+/// Update the sun's color when the theme changes
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn apply_theme(
+	mut events: EventReader<ThemeChanged>,
+	themes: Res<Assets<Theme>>,
+	mut sun: Query<&mut DirectionalLight>,
+) {
+	let Some(theme) = events.read().last().and_then(|e| themes.get(e.0)) else {
+		return;
+	};
+
+	for mut sun in &mut sun {
+		sun.color = theme.sun_light;
+	}
+}
@@ -0,0 +1,403 @@
+//! Collision of axis-aligned boxes (like the player) with the maze's walls,
+//! computed directly from the maze's tiles.
+
+use bevy::{math::bounding::Aabb2d, prelude::*};
+
+use crate::maze::{
+	tile_position,
+	Direction::{Bottom, Left, Right, Top},
+	Tile, MAZE_SIZE, TILE_SCALE, TILE_SIZE, WALL_THICKNESS,
+};
+
+/// The distance kept between a moving box and the walls it collides with, so
+/// that it can slide along walls without catching on the seams between them
+const SKIN: f32 = 0.01;
+/// The maximum number of walls a box can slide along in a single movement
+const MAX_SLIDES: usize = 4;
+
+/// Get the solid parts of the tile at index `i` in world coordinates
+///
+/// Every closed side of a tile is a wall `WALL_THICKNESS` thick spanning the
+/// whole side, and the corner flags in the tile's upper bits add the corners
+/// where walls of neighbouring tiles meet. Grass tiles have no walls.
+pub fn tile_walls(i: usize, tile: Tile) -> impl Iterator<Item = Aabb2d> {
+	let center = tile_position(u32::try_from(i).unwrap());
+	let half = TILE_SIZE * TILE_SCALE / 2.0;
+	let thickness = WALL_THICKNESS * TILE_SCALE;
+	let corner = Vec2::splat(thickness / 2.0);
+
+	let side = |closed: bool, offset: Vec2, half_size: Vec2| {
+		(closed && !tile.is_grass()).then(|| Aabb2d::new(center + offset, half_size))
+	};
+
+	let horizontal = Vec2::new(half.x, thickness / 2.0);
+	let vertical = Vec2::new(thickness / 2.0, half.y);
+	let edge = half - corner;
+
+	[
+		side(tile.is_closed(Top), Vec2::new(0.0, edge.y), horizontal),
+		side(tile.is_closed(Right), Vec2::new(edge.x, 0.0), vertical),
+		side(tile.is_closed(Bottom), Vec2::new(0.0, -edge.y), horizontal),
+		side(tile.is_closed(Left), Vec2::new(-edge.x, 0.0), vertical),
+		side(
+			tile.0 & 0b1000_0000 != 0,
+			Vec2::new(-edge.x, edge.y),
+			corner,
+		),
+		side(tile.0 & 0b0100_0000 != 0, Vec2::new(edge.x, edge.y), corner),
+		side(
+			tile.0 & 0b0010_0000 != 0,
+			Vec2::new(-edge.x, -edge.y),
+			corner,
+		),
+		side(
+			tile.0 & 0b0001_0000 != 0,
+			Vec2::new(edge.x, -edge.y),
+			corner,
+		),
+	]
+	.into_iter()
+	.flatten()
+}
+
+/// Get all walls of the tiles in `maze` which overlap `area`
+#[allow(
+	clippy::cast_possible_truncation,
+	clippy::cast_precision_loss,
+	clippy::cast_sign_loss
+)]
+pub fn walls_near(maze: &[Tile], area: Aabb2d) -> Vec<Aabb2d> {
+	let tile_size = TILE_SIZE * TILE_SCALE;
+	let to_tile = |pos: Vec2| (pos / tile_size + 0.5).floor() + (MAZE_SIZE / 2).as_vec2();
+
+	let min = to_tile(area.min)
+		.max(Vec2::ZERO)
+		.min((MAZE_SIZE - 1).as_vec2());
+	let max = to_tile(area.max)
+		.max(Vec2::ZERO)
+		.min((MAZE_SIZE - 1).as_vec2());
+
+	let mut walls = Vec::new();
+
+	for y in min.y as u32..=max.y as u32 {
+		for x in min.x as u32..=max.x as u32 {
+			let i = usize::try_from(y * MAZE_SIZE.x + x).unwrap();
+			walls.extend(tile_walls(i, maze[i]));
+		}
+	}
+
+	walls
+}
+
+/// Get the time (as a fraction of `delta`) at which a box with the given
+/// `half_size` moving from `from` by `delta` first touches `wall`, and the
+/// normal of the touched side
+///
+/// Walls which the box already overlaps at `from` are ignored
+fn time_of_impact(wall: Aabb2d, half_size: Vec2, from: Vec2, delta: Vec2) -> Option<(f32, Vec2)> {
+	let min = wall.min - half_size;
+	let max = wall.max + half_size;
+
+	let mut enter = f32::NEG_INFINITY;
+	let mut exit = f32::INFINITY;
+	let mut normal = Vec2::ZERO;
+
+	for (axis, unit) in [(0, Vec2::X), (1, Vec2::Y)] {
+		if delta[axis] == 0.0 {
+			if from[axis] <= min[axis] || from[axis] >= max[axis] {
+				return None;
+			}
+
+			continue;
+		}
+
+		let t_min = (min[axis] - from[axis]) / delta[axis];
+		let t_max = (max[axis] - from[axis]) / delta[axis];
+		let (t_enter, t_exit) = if t_min < t_max {
+			(t_min, t_max)
+		} else {
+			(t_max, t_min)
+		};
+
+		if t_enter > enter {
+			enter = t_enter;
+			normal = -unit * delta[axis].signum();
+		}
+
+		exit = exit.min(t_exit);
+	}
+
+	(enter < exit && (0.0..=1.0).contains(&enter)).then_some((enter, normal))
+}
+
+/// Move a box with the given `half_size` from `from` to `to`, stopping at and
+/// sliding along the `walls` in the way, and return its final position
+///
+/// The movement is swept, so walls can not be skipped at high speeds. If the
+/// box ends up overlapping a wall (e.g. because it started inside of one), it
+/// is pushed out of the wall by the shortest distance.
+pub fn sweep(walls: &[Aabb2d], half_size: Vec2, from: Vec2, to: Vec2) -> Vec2 {
+	let mut pos = from;
+	let mut delta = to - from;
+
+	for _ in 0..MAX_SLIDES {
+		if delta == Vec2::ZERO {
+			break;
+		}
+
+		let hit = walls
+			.iter()
+			.filter_map(|&wall| time_of_impact(wall, half_size, pos, delta))
+			.min_by(|(a, _), (b, _)| a.total_cmp(b));
+
+		let Some((time, normal)) = hit else {
+			pos += delta;
+			break;
+		};
+
+		pos += delta * time + normal * SKIN;
+		delta *= 1.0 - time;
+		delta -= normal * delta.dot(normal);
+	}
+
+	depenetrate(walls, half_size, pos)
+}
+
+/// Push a box with the given `half_size` at `pos` out of all `walls` it
+/// overlaps, along the axis of least penetration
+pub fn depenetrate(walls: &[Aabb2d], half_size: Vec2, mut pos: Vec2) -> Vec2 {
+	for wall in walls {
+		let overlap_min = (pos + half_size) - wall.min;
+		let overlap_max = wall.max - (pos - half_size);
+
+		if overlap_min.cmple(Vec2::ZERO).any() || overlap_max.cmple(Vec2::ZERO).any() {
+			continue;
+		}
+
+		let push = Vec2::new(
+			if overlap_min.x < overlap_max.x {
+				-overlap_min.x
+			} else {
+				overlap_max.x
+			},
+			if overlap_min.y < overlap_max.y {
+				-overlap_min.y
+			} else {
+				overlap_max.y
+			},
+		);
+
+		if push.x.abs() < push.y.abs() {
+			pos.x += push.x;
+		} else {
+			pos.y += push.y;
+		}
+	}
+
+	pos
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::maze::START;
+
+	const HALF_SIZE: Vec2 = Vec2::splat(5.0);
+
+	/// Whether a box with the given `half_size` at `pos` overlaps `wall`
+	fn overlaps(wall: Aabb2d, half_size: Vec2, pos: Vec2) -> bool {
+		(pos + half_size).cmpgt(wall.min).all() && (pos - half_size).cmplt(wall.max).all()
+	}
+
+	#[test]
+	fn moves_freely_without_walls() {
+		let to = sweep(&[], HALF_SIZE, Vec2::ZERO, Vec2::new(30.0, -20.0));
+		assert_eq!(to, Vec2::new(30.0, -20.0));
+	}
+
+	#[test]
+	fn stops_at_wall() {
+		let wall = Aabb2d::new(Vec2::new(50.0, 0.0), Vec2::new(5.0, 100.0));
+		let to = sweep(&[wall], HALF_SIZE, Vec2::ZERO, Vec2::new(100.0, 0.0));
+
+		assert!((to.x - 40.0).abs() <= SKIN * 2.0, "stopped at {to}");
+		assert!(to.y.abs() <= f32::EPSILON, "stopped at {to}");
+		assert!(!overlaps(wall, HALF_SIZE, to));
+	}
+
+	#[test]
+	fn slides_along_wall() {
+		let wall = Aabb2d::new(Vec2::new(0.0, -20.0), Vec2::new(100.0, 5.0));
+		let to = sweep(&[wall], HALF_SIZE, Vec2::ZERO, Vec2::new(30.0, -30.0));
+
+		assert!((to.x - 30.0).abs() <= SKIN, "slid to {to}");
+		assert!((to.y - -10.0).abs() <= SKIN * 2.0, "slid to {to}");
+		assert!(!overlaps(wall, HALF_SIZE, to));
+	}
+
+	#[test]
+	fn slides_across_seams() {
+		// A floor made of several walls touching each other, like the walls of
+		// neighbouring tiles
+		let walls = (-5i8..5)
+			.map(|i| Aabb2d::new(Vec2::new(f32::from(i) * 20.0, -20.0), Vec2::new(10.0, 5.0)))
+			.collect::<Vec<_>>();
+		let to = sweep(&walls, HALF_SIZE, Vec2::ZERO, Vec2::new(80.0, -20.0));
+
+		assert!((to.x - 80.0).abs() <= SKIN, "slid to {to}");
+		assert!(walls.iter().all(|&w| !overlaps(w, HALF_SIZE, to)));
+	}
+
+	#[test]
+	fn stops_at_corner() {
+		// A box touching a wall's corner exactly diagonally
+		let wall = Aabb2d::new(Vec2::new(20.0, 20.0), Vec2::splat(5.0));
+		let to = sweep(&[wall], HALF_SIZE, Vec2::ZERO, Vec2::splat(20.0));
+
+		assert!(!overlaps(wall, HALF_SIZE, to), "ended up at {to}");
+		assert!((to - 10.0).min_element() <= SKIN * 2.0, "ended up at {to}");
+	}
+
+	#[test]
+	fn stops_in_inner_corner() {
+		let right = Aabb2d::new(Vec2::new(20.0, 0.0), Vec2::new(5.0, 100.0));
+		let top = Aabb2d::new(Vec2::new(0.0, 20.0), Vec2::new(100.0, 5.0));
+		let to = sweep(&[right, top], HALF_SIZE, Vec2::ZERO, Vec2::new(50.0, 30.0));
+
+		assert!(
+			(to - Vec2::splat(10.0)).abs().max_element() <= SKIN * 2.0,
+			"stopped at {to}"
+		);
+		assert!(!overlaps(right, HALF_SIZE, to));
+		assert!(!overlaps(top, HALF_SIZE, to));
+	}
+
+	#[test]
+	fn does_not_tunnel_at_high_speed() {
+		// Thin walls which a box would skip over if only its final position was
+		// checked
+		let walls = (1u8..=8)
+			.map(|i| Aabb2d::new(Vec2::new(f32::from(i) * 100.0, 0.0), Vec2::new(0.5, 100.0)))
+			.collect::<Vec<_>>();
+		let to = sweep(&walls, HALF_SIZE, Vec2::ZERO, Vec2::new(100_000.0, 0.0));
+
+		assert!(to.x < 100.0, "tunnelled to {to}");
+		assert!(walls.iter().all(|&w| !overlaps(w, HALF_SIZE, to)));
+	}
+
+	#[test]
+	fn does_not_tunnel_when_out_of_slides() {
+		// A closed room, in which a fast diagonal movement hits more walls than
+		// the box can slide along
+		let walls = [
+			Aabb2d::new(Vec2::new(30.0, 0.0), Vec2::new(5.0, 40.0)),
+			Aabb2d::new(Vec2::new(-30.0, 0.0), Vec2::new(5.0, 40.0)),
+			Aabb2d::new(Vec2::new(0.0, 30.0), Vec2::new(40.0, 5.0)),
+			Aabb2d::new(Vec2::new(0.0, -30.0), Vec2::new(40.0, 5.0)),
+		];
+
+		for delta in [
+			Vec2::new(10_000.0, 7_000.0),
+			Vec2::new(-10_000.0, 3_000.0),
+			Vec2::new(1.0, -10_000.0),
+		] {
+			let to = sweep(&walls, HALF_SIZE, Vec2::ZERO, delta);
+
+			assert!(to.abs().max_element() <= 20.0, "left the room at {to}");
+			assert!(walls.iter().all(|&w| !overlaps(w, HALF_SIZE, to)));
+		}
+	}
+
+	#[test]
+	fn depenetrates_along_shortest_axis() {
+		let wall = Aabb2d::new(Vec2::new(0.0, 0.0), Vec2::new(50.0, 5.0));
+		let to = depenetrate(&[wall], HALF_SIZE, Vec2::new(10.0, 7.0));
+
+		assert_eq!(to, Vec2::new(10.0, 10.0));
+	}
+
+	#[test]
+	fn sweep_depenetrates_overlapping_start() {
+		let wall = Aabb2d::new(Vec2::new(0.0, 0.0), Vec2::new(5.0, 50.0));
+		let to = sweep(
+			&[wall],
+			HALF_SIZE,
+			Vec2::new(-7.0, 0.0),
+			Vec2::new(-7.0, 20.0),
+		);
+
+		assert!(!overlaps(wall, HALF_SIZE, to), "still overlapping at {to}");
+		assert_eq!(to, Vec2::new(-10.0, 20.0));
+	}
+
+	#[test]
+	fn time_of_impact_ignores_overlapping_walls() {
+		let wall = Aabb2d::new(Vec2::ZERO, Vec2::splat(5.0));
+
+		assert_eq!(time_of_impact(wall, HALF_SIZE, Vec2::ZERO, Vec2::X), None);
+		assert_eq!(
+			time_of_impact(wall, HALF_SIZE, Vec2::new(-20.0, 0.0), Vec2::new(20.0, 0.0)),
+			Some((0.5, Vec2::NEG_X))
+		);
+	}
+
+	#[test]
+	fn closed_tile_has_all_walls() {
+		let i = usize::try_from(START.index()).unwrap();
+		let center = tile_position(START.index());
+		let half = TILE_SIZE * TILE_SCALE / 2.0;
+		let walls = tile_walls(i, Tile::CLOSED).collect::<Vec<_>>();
+
+		// Four sides and four corners, all within the tile
+		assert_eq!(walls.len(), 8);
+		assert!(walls
+			.iter()
+			.all(|w| w.min.cmpge(center - half).all() && w.max.cmple(center + half).all()));
+
+		// Every edge of the tile is covered by a wall
+		for edge in [
+			Vec2::new(0.0, half.y),
+			Vec2::new(half.x, 0.0),
+			Vec2::new(0.0, -half.y),
+			Vec2::new(-half.x, 0.0),
+			half,
+			-half,
+		] {
+			let point = center + edge * 0.99;
+			assert!(
+				walls
+					.iter()
+					.any(|w| point.cmpge(w.min).all() && point.cmple(w.max).all()),
+				"{point} is not in a wall"
+			);
+		}
+
+		// The center of the tile is free
+		assert!(walls.iter().all(|w| !overlaps(*w, HALF_SIZE, center)));
+	}
+
+	#[test]
+	fn only_closed_sides_have_walls() {
+		let i = usize::try_from(START.index()).unwrap();
+
+		assert_eq!(tile_walls(i, Tile(0)).count(), 0);
+		assert_eq!(tile_walls(i, Tile(0b1010_1111)).count(), 0);
+		assert_eq!(tile_walls(i, Tile(0b1000)).count(), 1);
+	}
+
+	#[test]
+	fn finds_walls_near_area() {
+		let mut maze = vec![Tile(0); usize::try_from(MAZE_SIZE.x * MAZE_SIZE.y).unwrap()];
+		maze[usize::try_from(START.index()).unwrap()] = Tile::CLOSED;
+
+		let center = tile_position(START.index());
+		let near = walls_near(&maze, Aabb2d::new(center, HALF_SIZE));
+		assert_eq!(near.len(), 8);
+
+		let far = tile_position(START.index() + 3);
+		assert!(walls_near(&maze, Aabb2d::new(far, HALF_SIZE)).is_empty());
+
+		// Areas outside of the maze are clamped to its edges
+		assert!(walls_near(&maze, Aabb2d::new(Vec2::splat(1e9), HALF_SIZE)).is_empty());
+	}
+}
@@ -0,0 +1,95 @@
+//! Commands controlling the game from outside of it, sent by the page embedding
+//! the wasm build (see the `api` module) or on native by a launcher through the
+//! events socket (see the `ipc` module).
+//!
+//! Commands are queued when they are sent, and executed during the next frame.
+
+use std::sync::{Mutex, PoisonError};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::Deserialize;
+
+use crate::{algorithms::MazeParams, maze::RegenerateMaze, state::AppState};
+
+/// The commands which were sent, but not executed yet
+static QUEUE: Mutex<Vec<Command>> = Mutex::new(Vec::new());
+
+/// A command sent from outside of the game
+///
+/// As JSON, this is an object with the command's name in kebab-case as its
+/// `command` property, along with the command's fields, like `{"command":
+/// "regenerate", "seed": 42}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Command {
+	/// Set the parameters of the next generated maze
+	SetParams(MazeParams),
+	/// Generate a new maze with the current parameters and the given seed, or
+	/// a random seed if there is none
+	Regenerate { seed: Option<u64> },
+	/// Open the menu, pausing the game
+	OpenMenu,
+	/// Close the menu, resuming the game
+	CloseMenu,
+	/// Quit the game, which is only possible on native
+	Quit,
+}
+
+impl Command {
+	/// Check that the command can be executed
+	pub fn validate(self) -> Result<Self, String> {
+		match self {
+			Self::SetParams(params) if !params.is_valid() => {
+				Err(format!("invalid maze parameters: {params:?}"))
+			}
+			Self::Quit if cfg!(target_arch = "wasm32") => {
+				Err("the game can not be quit on wasm".to_string())
+			}
+			_ => Ok(self),
+		}
+	}
+
+	/// Queue the command to be executed during the next frame
+	pub fn send(self) {
+		QUEUE
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.push(self);
+	}
+}
+
+/// Execute the queued commands
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn execute(
+	mut params: ResMut<MazeParams>,
+	mut regenerate: EventWriter<RegenerateMaze>,
+	state: Res<State<AppState>>,
+	mut next: ResMut<NextState<AppState>>,
+	mut exit: EventWriter<AppExit>,
+) {
+	let commands = std::mem::take(&mut *QUEUE.lock().unwrap_or_else(PoisonError::into_inner));
+
+	for command in commands {
+		match command {
+			Command::SetParams(new) => {
+				params.set_if_neq(new);
+			}
+			Command::Regenerate { seed } => {
+				regenerate.send(RegenerateMaze { seed });
+			}
+			Command::OpenMenu => {
+				if matches!(state.get(), AppState::Playing | AppState::Won) {
+					next.set(AppState::Paused);
+				}
+			}
+			Command::CloseMenu => {
+				if matches!(state.get(), AppState::Menu | AppState::Paused) {
+					next.set(AppState::Playing);
+				}
+			}
+			Command::Quit => {
+				exit.send(AppExit);
+			}
+		}
+	}
+}
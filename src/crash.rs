@@ -0,0 +1,118 @@
+//! Crash reports, saved when the game panics and offered to the player on the
+//! next launch, so that they can be attached to issues.
+//!
+//! The report of the last crash is saved as `crash.txt` in the config directory
+//! on native and in `localStorage` on wasm, until the player dismisses it.
+
+use std::{
+	fmt::Write,
+	sync::{Mutex, PoisonError},
+};
+
+use bevy::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::{algorithms::MazeParams, maze::MazeRegenerated, storage};
+
+/// The name of the file the crash report is saved in
+const FILE_NAME: &str = "crash.txt";
+
+/// The parameters and seed of the current maze, included in crash reports
+static CURRENT_MAZE: Mutex<Option<(MazeParams, u64)>> = Mutex::new(None);
+
+/// The clipboard the crash report is copied to, which has to be kept open on
+/// some platforms for the copied text to stay available
+#[cfg(not(target_arch = "wasm32"))]
+static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// The crash report saved during a previous launch, if it was not dismissed
+#[derive(Debug, Clone, Default, Resource)]
+pub struct LastCrash(pub Option<String>);
+
+impl LastCrash {
+	/// Forget the crash report, so that it is not offered again
+	pub fn dismiss(&mut self) {
+		if self.0.take().is_some() {
+			storage::remove_config(FILE_NAME);
+		}
+	}
+}
+
+/// Save a crash report with the panic message `panic`, the game's version and
+/// the current maze
+///
+/// This should be called from a panic handler or hook
+pub fn save(panic: &str) {
+	let mut report = format!(
+		"maze {} ({} {})\n",
+		env!("CARGO_PKG_VERSION"),
+		std::env::consts::OS,
+		std::env::consts::ARCH,
+	);
+
+	// The lock is only held briefly, but it must not be waited for while
+	// panicking
+	match CURRENT_MAZE.try_lock().ok().and_then(|maze| *maze) {
+		Some((params, seed)) => {
+			let _ = writeln!(report, "maze parameters: {params:?}, seed: {seed:x}");
+		}
+		None => report.push_str("no maze was generated\n"),
+	}
+
+	report.push('\n');
+	report.push_str(panic);
+
+	storage::save_config(FILE_NAME, &report);
+}
+
+/// Copy the crash report to the clipboard
+///
+/// # Errors
+/// Returns an error if the clipboard is not available
+#[cfg(not(target_arch = "wasm32"))]
+pub fn copy(report: &str) -> Result<(), String> {
+	let mut clipboard = CLIPBOARD.lock().unwrap_or_else(PoisonError::into_inner);
+
+	if clipboard.is_none() {
+		*clipboard = Some(arboard::Clipboard::new().map_err(|e| e.to_string())?);
+	}
+
+	clipboard
+		.as_mut()
+		.map_or(Ok(()), |clipboard| clipboard.set_text(report))
+		.map_err(|e| e.to_string())
+}
+
+/// Copy the crash report to the clipboard
+///
+/// # Errors
+/// Returns an error if the clipboard is not available (like outside of secure
+/// contexts). The browser copies the text asynchronously, so later errors are
+/// only logged to the console by the browser
+#[cfg(target_arch = "wasm32")]
+pub fn copy(report: &str) -> Result<(), String> {
+	#[wasm_bindgen]
+	extern "C" {
+		#[wasm_bindgen(catch, js_namespace = ["navigator", "clipboard"], js_name = writeText)]
+		fn write_text(text: &str) -> Result<js_sys::Promise, JsValue>;
+	}
+
+	write_text(report)
+		.map(|_| ())
+		.map_err(|e| e.as_string().unwrap_or_else(|| format!("{e:?}")))
+}
+
+pub fn initialize(mut commands: Commands) {
+	let report = storage::load_config(FILE_NAME).filter(|report| !report.trim().is_empty());
+	commands.insert_resource(LastCrash(report));
+}
+
+/// Remember the current maze for crash reports
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn track(mut regenerated: EventReader<MazeRegenerated>) {
+	if let Some(event) = regenerated.read().last() {
+		*CURRENT_MAZE.lock().unwrap_or_else(PoisonError::into_inner) =
+			Some((event.params, event.seed));
+	}
+}
@@ -1,28 +1,76 @@
-//! Game-generated JavaScript events
+//! Game-generated events
+//!
+//! On wasm, events are dispatched as JavaScript `CustomEvent`s on `window`. On
+//! native, they are written as JSON lines (like
+//! `{"event":"maze-generated","time":1.5,"detail":{...}}`) to the file given
+//! with the `--events <path>` command line option (or to stdout if the path is
+//! `-`), or on Unix to the events socket given with `--events-socket <path>`
+//! (see the `ipc` module). The lines are written on a separate thread, so a
+//! slow reader can not stall the game - events are dropped instead if too many
+//! of them are waiting to be written.
 
 use std::{
 	fmt::{Display, Formatter, Result as FmtResult},
 	sync::{Once, OnceLock},
 };
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+	fs::File,
+	io::{self, Write},
+	sync::mpsc::{self, Receiver, SyncSender},
+	thread,
+};
 
 use bevy::{
 	prelude::*,
 	utils::{Duration, Instant},
 };
+use serde_json::{json, Value};
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 #[cfg(target_arch = "wasm32")]
 use web_sys::{CustomEvent, CustomEventInit, Event};
 
+use crate::{
+	algorithms::MazeParams,
+	ghost::CurrentRun,
+	maze::{MazeRegenerated, MazeStats},
+	settings::Settings,
+};
+
 static STARTUP_TIME: OnceLock<Instant> = OnceLock::new();
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The queue of the thread writing events to the output on native, if there
+/// is an output
+#[cfg(not(target_arch = "wasm32"))]
+static OUTPUT: OnceLock<SyncSender<Output>> = OnceLock::new();
+
+/// The number of events which can wait to be written before new ones are
+/// dropped
+#[cfg(not(target_arch = "wasm32"))]
+const OUTPUT_CAPACITY: usize = 256;
+
+/// How long to wait for the queued events to be written when panicking
+#[cfg(not(target_arch = "wasm32"))]
+const PANIC_FLUSH_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A message for the thread writing events to the output
+#[cfg(not(target_arch = "wasm32"))]
+enum Output {
+	/// Write a JSON line
+	Line(String),
+	/// Notify the sender once all the previous lines were written
+	Flush(SyncSender<()>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum RunEvent {
 	/// The application loaded (near the beginning of `main`)
 	///
 	/// Contains the name of the game which is being loaded
 	Loaded(&'static str),
-	/// The application has initialized (after the startup systems have run)
+	/// The application has initialized (after the startup systems have run and
+	/// the first maze's textures were generated)
 	///
 	/// Contains the time since application startup
 	Initialized(Option<Duration>),
@@ -37,20 +85,79 @@ pub enum RunEvent {
 	///
 	/// This event is also dispatched on wasm if `main` returns
 	Panicked(Option<String>),
+	/// A new maze was generated
+	Generated {
+		params: MazeParams,
+		seed: u64,
+		stats: MazeStats,
+	},
+	/// The player reached the exit of the current maze
+	///
+	/// Contains the time and number of movement steps the run took
+	ExitReached { time: Duration, steps: u32 },
+	/// The menu was opened
+	MenuOpened,
+	/// The menu was closed
+	MenuClosed,
+	/// The settings were changed
+	///
+	/// Contains the new settings
+	SettingsChanged(Settings),
 }
 
 impl RunEvent {
 	/// Get the name of this event
 	///
-	/// On `wasm32` this is the `type` of the `CustomEvent` for this `RunEvent`
+	/// On `wasm32` this is the `type` of the `CustomEvent` for this `RunEvent`,
+	/// and on native the `event` of its JSON line
 	#[must_use]
-	#[cfg(target_arch = "wasm32")]
 	pub const fn name(&self) -> &'static str {
 		match self {
 			Self::Loaded(_) => "maze-load",
 			Self::Initialized(_) => "maze-init",
 			Self::Started(_) => "maze-start",
 			Self::Panicked(_) => "maze-panic",
+			Self::Generated { .. } => "maze-generated",
+			Self::ExitReached { .. } => "maze-exit-reached",
+			Self::MenuOpened => "maze-menu-opened",
+			Self::MenuClosed => "maze-menu-closed",
+			Self::SettingsChanged(_) => "maze-settings-changed",
+		}
+	}
+
+	/// Get the details of this `RunEvent` as JSON (type depending on the
+	/// `RunEvent` variant)
+	///
+	/// Durations are in seconds, and seeds are strings, since JS numbers can
+	/// not represent all seeds.
+	#[must_use]
+	pub fn details_json(&self) -> Value {
+		match self {
+			Self::Loaded(s) => json!(s),
+			Self::Initialized(None)
+			| Self::Started(None)
+			| Self::Panicked(None)
+			| Self::MenuOpened
+			| Self::MenuClosed => Value::Null,
+			Self::Initialized(Some(d)) | Self::Started(Some(d)) => json!(d.as_secs_f64()),
+			Self::Panicked(Some(s)) => json!(s),
+			Self::Generated {
+				params,
+				seed,
+				stats,
+			} => json!({
+				"params": params,
+				"seed": seed.to_string(),
+				"stats": {
+					"solution_length": stats.solution_length,
+					"dead_ends": stats.dead_ends,
+				},
+			}),
+			Self::ExitReached { time, steps } => json!({
+				"time": time.as_secs_f64(),
+				"steps": steps,
+			}),
+			Self::SettingsChanged(settings) => json!(settings),
 		}
 	}
 
@@ -59,13 +166,10 @@ impl RunEvent {
 	#[cfg(target_arch = "wasm32")]
 	#[must_use]
 	pub fn details(&self) -> JsValue {
-		match self {
-			Self::Loaded(s) => JsValue::from_str(s),
-			Self::Initialized(None) | Self::Started(None) | Self::Panicked(None) => JsValue::null(),
-			Self::Initialized(Some(d)) | Self::Started(Some(d)) => {
-				JsValue::from_f64(d.as_secs_f64())
-			}
-			Self::Panicked(Some(s)) => JsValue::from_str(s),
+		match self.details_json() {
+			Value::Null => JsValue::null(),
+			Value::String(s) => JsValue::from_str(&s),
+			details => js_sys::JSON::parse(&details.to_string()).unwrap_or(JsValue::null()),
 		}
 	}
 
@@ -100,15 +204,140 @@ impl Display for RunEvent {
 			}
 			Self::Panicked(None) => f.write_str("game panicked"),
 			Self::Panicked(Some(d)) => f.write_fmt(format_args!("game panicked:\n{d}")),
+			Self::Generated { params, seed, .. } => f.write_fmt(format_args!(
+				"generated {}x{} maze (seed {seed:x})",
+				params.width, params.height
+			)),
+			Self::ExitReached { time, steps } => f.write_fmt(format_args!(
+				"exit reached in {:.2}s ({steps} steps)",
+				time.as_secs_f32()
+			)),
+			Self::MenuOpened => f.write_str("menu opened"),
+			Self::MenuClosed => f.write_str("menu closed"),
+			Self::SettingsChanged(_) => f.write_str("settings changed"),
 		}
 	}
 }
 
-/// Initialize event time measurements
+/// Initialize event time measurements, and on native open the output events
+/// are written to
 ///
 /// This should be called as early as possible during application startup
 pub fn init() {
 	STARTUP_TIME.get_or_init(Instant::now);
+
+	#[cfg(not(target_arch = "wasm32"))]
+	if let Some(output) = open_output() {
+		let (sender, receiver) = mpsc::sync_channel(OUTPUT_CAPACITY);
+
+		let spawned = thread::Builder::new()
+			.name("events output".to_string())
+			.spawn(move || write(output, &receiver));
+
+		match spawned {
+			Ok(_) => {
+				OUTPUT.get_or_init(|| sender);
+			}
+			Err(e) => eprintln!("could not write the events output: {e}"),
+		}
+	}
+}
+
+/// Write the queued events to `output` until the game exits
+///
+/// The events are not worth stopping the game over, and logging the errors
+/// while panicking could panic again, so write errors are ignored.
+#[cfg(not(target_arch = "wasm32"))]
+fn write(mut output: Box<dyn Write + Send>, receiver: &Receiver<Output>) {
+	for message in receiver {
+		match message {
+			Output::Line(line) => {
+				let _ = writeln!(output, "{line}").and_then(|()| output.flush());
+			}
+			Output::Flush(done) => {
+				let _ = done.send(());
+			}
+		}
+	}
+}
+
+/// Wait until the events dispatched so far are written to the output, or
+/// until `timeout` passes
+#[cfg(not(target_arch = "wasm32"))]
+fn flush(timeout: Duration) {
+	let Some(output) = OUTPUT.get() else {
+		return;
+	};
+
+	let (done, written) = mpsc::sync_channel(1);
+
+	if output.try_send(Output::Flush(done)).is_ok() {
+		let _ = written.recv_timeout(timeout);
+	}
+}
+
+/// Open the output given on the command line, preferring the events socket
+#[cfg(not(target_arch = "wasm32"))]
+fn open_output() -> Option<Box<dyn Write + Send>> {
+	#[cfg(unix)]
+	if let Some(socket) = crate::ipc::connect() {
+		return Some(Box::new(socket));
+	}
+
+	let path = arg("--events")?;
+	let output: io::Result<Box<dyn Write + Send>> = if path == "-" {
+		Ok(Box::new(io::stdout()))
+	} else {
+		File::create(&path).map(|file| Box::new(file) as _)
+	};
+
+	output
+		.map_err(|e| eprintln!("could not open the events output {path:?}: {e}"))
+		.ok()
+}
+
+/// Get the value of the command line option `name`, given either as `name
+/// value` or `name=value`
+#[cfg(not(target_arch = "wasm32"))]
+pub fn arg(name: &str) -> Option<String> {
+	let mut args = std::env::args().skip(1);
+
+	while let Some(arg) = args.next() {
+		if arg == name {
+			return args.next();
+		}
+
+		if let Some(value) = arg.strip_prefix(name).and_then(|a| a.strip_prefix('=')) {
+			return Some(value.to_string());
+		}
+	}
+
+	None
+}
+
+/// Dispatch `event` as a JS `CustomEvent` on wasm, or queue it to be written to
+/// the events output on native (dropping it if the queue is full)
+///
+/// # Panics
+/// This function panics on `wasm32` if the `window` JS object or its
+/// `dispatchEvent` method are unavailable or throw
+pub fn dispatch(event: &RunEvent) {
+	#[cfg(target_arch = "wasm32")]
+	web_sys::window()
+		.expect("JS `window` not available")
+		.dispatch_event(&event.to_js())
+		.expect("JS `dispatchEvent` failed");
+
+	#[cfg(not(target_arch = "wasm32"))]
+	if let Some(output) = OUTPUT.get() {
+		let line = json!({
+			"event": event.name(),
+			"time": STARTUP_TIME.get().map(|t| t.elapsed().as_secs_f64()),
+			"detail": event.details_json(),
+		});
+
+		let _ = output.try_send(Output::Line(line.to_string()));
+	}
 }
 
 /// Dispatch the `Loaded` event once
@@ -124,11 +353,7 @@ pub fn loaded(game: &'static str) {
 	ONCE.call_once(|| {
 		let event = RunEvent::Loaded(game);
 
-		#[cfg(target_arch = "wasm32")]
-		web_sys::window()
-			.expect("JS `window` not available")
-			.dispatch_event(&event.to_js())
-			.expect("JS `dispatchEvent` failed");
+		dispatch(&event);
 
 		info!("{event}");
 	});
@@ -136,7 +361,9 @@ pub fn loaded(game: &'static str) {
 
 /// Dispatch the `Initialized` event once
 ///
-/// This should be called after startup systems have run
+/// This should be called after startup systems have run and the first maze's
+/// textures were generated (i.e. when leaving
+/// [`AppState::Loading`](crate::state::AppState::Loading))
 ///
 /// # Panics
 /// This function panics on `wasm32` if the `window` JS object or its
@@ -148,11 +375,7 @@ pub fn initialized() {
 		let dur = STARTUP_TIME.get().map(Instant::elapsed);
 		let event = RunEvent::Initialized(dur);
 
-		#[cfg(target_arch = "wasm32")]
-		web_sys::window()
-			.expect("JS `window` not available")
-			.dispatch_event(&event.to_js())
-			.expect("JS `dispatchEvent` failed");
+		dispatch(&event);
 
 		info!("{event}");
 	});
@@ -177,11 +400,7 @@ pub fn started() {
 			let dur = STARTUP_TIME.get().map(Instant::elapsed);
 			let event = RunEvent::Started(dur);
 
-			#[cfg(target_arch = "wasm32")]
-			web_sys::window()
-				.expect("JS `window` not available")
-				.dispatch_event(&event.to_js())
-				.expect("JS `dispatchEvent` failed");
+			dispatch(&event);
 
 			info!("{event}");
 		});
@@ -200,11 +419,59 @@ pub fn started() {
 pub fn panic(info: String) {
 	let event = RunEvent::Panicked(Some(info));
 
-	#[cfg(target_arch = "wasm32")]
-	web_sys::window()
-		.expect("JS `window` not available")
-		.dispatch_event(&event.to_js())
-		.expect("JS `dispatchEvent` failed");
+	dispatch(&event);
+
+	// The game exits after panicking, so give the output a chance to get the
+	// event
+	#[cfg(not(target_arch = "wasm32"))]
+	flush(PANIC_FLUSH_TIMEOUT);
 
 	error!("{event}");
 }
+
+/// Dispatch the `Generated` event for every generated maze
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn generated(mut regenerated: EventReader<MazeRegenerated>) {
+	for event in regenerated.read() {
+		dispatch(&RunEvent::Generated {
+			params: event.params,
+			seed: event.seed,
+			stats: event.stats,
+		});
+	}
+}
+
+/// Dispatch the `ExitReached` event when the current run is finished
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn exit_reached(run: Res<CurrentRun>, mut finished: Local<bool>) {
+	let Some(run) = &run.0 else {
+		return;
+	};
+
+	if run.finished && !*finished {
+		dispatch(&RunEvent::ExitReached {
+			time: run.elapsed,
+			steps: run.steps(),
+		});
+	}
+
+	*finished = run.finished;
+}
+
+/// Dispatch the `MenuOpened` event
+pub fn menu_opened() {
+	dispatch(&RunEvent::MenuOpened);
+}
+
+/// Dispatch the `MenuClosed` event
+pub fn menu_closed() {
+	dispatch(&RunEvent::MenuClosed);
+}
+
+/// Dispatch the `SettingsChanged` event when the settings change
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn settings_changed(settings: Res<Settings>) {
+	if settings.is_changed() && !settings.is_added() {
+		dispatch(&RunEvent::SettingsChanged(*settings));
+	}
+}
@@ -0,0 +1,177 @@
+//! Fog of war, hiding tiles which are not in any player's line of sight.
+
+use bevy::{
+	pbr::{NotShadowCaster, NotShadowReceiver},
+	prelude::*,
+};
+
+use crate::{
+	algorithms::line_of_sight,
+	bindings::{Action, Actions},
+	maze::{nearest_tile, Maze, Tile, TilePos, TileSet, MAZE_SIZE, TILE_SIZE},
+	minimap::Explored,
+	player::Player,
+};
+
+/// The distance (in tiles) the player can see
+const SIGHT_RADIUS: u32 = 8;
+/// The opacity of the fog over tiles which have been seen before
+const REMEMBERED_OPACITY: f32 = 0.6;
+/// The height of the fog overlays in tile-local coordinates, just below the 3D
+/// camera (at `z = 10.0` in world coordinates) so that walls are covered too
+const OVERLAY_HEIGHT: f32 = 1.9;
+
+/// The set of tiles currently in the player's line of sight
+#[derive(Debug, Clone, Default, Resource, Deref, DerefMut)]
+pub struct Visible(pub TileSet);
+
+/// Fog of war settings and assets
+#[derive(Debug, Clone, Resource)]
+pub struct Fog {
+	/// Whether tiles outside of the player's line of sight are hidden
+	pub enabled: bool,
+	mesh: Handle<Mesh>,
+	hidden_material: Handle<StandardMaterial>,
+	remembered_material: Handle<StandardMaterial>,
+}
+
+/// The fog overlay over the tile at the given position
+#[derive(Debug, Clone, Copy, Component)]
+pub struct FogOverlay(TilePos);
+
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn initialize(
+	mut commands: Commands,
+	mut meshes: ResMut<Assets<Mesh>>,
+	mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+	let hidden_material = materials.add(StandardMaterial {
+		base_color: Color::BLACK,
+		unlit: true,
+		fog_enabled: false,
+		..default()
+	});
+
+	let remembered_material = materials.add(StandardMaterial {
+		base_color: Color::rgba(0.0, 0.0, 0.0, REMEMBERED_OPACITY),
+		alpha_mode: AlphaMode::Blend,
+		unlit: true,
+		fog_enabled: false,
+		..default()
+	});
+
+	commands.insert_resource(Fog {
+		enabled: false,
+		mesh: meshes.add(Rectangle::from_size(TILE_SIZE)),
+		hidden_material,
+		remembered_material,
+	});
+	commands.insert_resource(Visible::default());
+}
+
+/// Turn the fog of war on or off ([`Action::Fog`])
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn toggle(mut fog: ResMut<Fog>, actions: Actions) {
+	if actions.just_pressed(Action::Fog) {
+		fog.enabled = !fog.enabled;
+	}
+}
+
+/// Update the tiles in the players' lines of sight and mark them as explored
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn update_line_of_sight(
+	mut visible: ResMut<Visible>,
+	mut explored: ResMut<Explored>,
+	maze: Res<Maze>,
+	players: Query<&Transform, With<Player>>,
+	mut last_players: Local<Vec<TilePos>>,
+) {
+	if maze.is_changed() {
+		explored.clear();
+		last_players.clear();
+	}
+
+	let positions = players
+		.iter()
+		.map(|player| nearest_tile(player.translation.truncate()))
+		.filter(|pos| pos.x < MAZE_SIZE.x && pos.y < MAZE_SIZE.y)
+		.collect::<Vec<_>>();
+
+	if positions.is_empty() || positions == *last_players {
+		return;
+	}
+
+	visible.clear();
+
+	for &pos in &positions {
+		for pos in line_of_sight(&maze, pos, SIGHT_RADIUS) {
+			visible.insert(pos);
+
+			if !explored.contains(pos) {
+				explored.insert(pos);
+			}
+		}
+	}
+
+	*last_players = positions;
+}
+
+/// Add fog overlays to newly spawned tiles inside the maze
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn spawn_overlays(
+	mut commands: Commands,
+	fog: Res<Fog>,
+	maze: Res<Maze>,
+	tiles: Query<(Entity, &TilePos), Added<Tile>>,
+) {
+	let params = maze.params;
+	let x_extent = params.margin_x()..params.margin_x() + params.width();
+	let y_extent = params.margin_y()..params.margin_y() + params.height();
+
+	for (entity, &pos) in &tiles {
+		if !x_extent.contains(&pos.x) || !y_extent.contains(&pos.y) {
+			continue;
+		}
+
+		commands.entity(entity).with_children(|builder| {
+			builder.spawn((
+				FogOverlay(pos),
+				NotShadowCaster,
+				NotShadowReceiver,
+				PbrBundle {
+					mesh: fog.mesh.clone(),
+					material: fog.hidden_material.clone(),
+					transform: Transform::from_xyz(0.0, 0.0, OVERLAY_HEIGHT),
+					visibility: Visibility::Hidden,
+					..default()
+				},
+			));
+		});
+	}
+}
+
+/// Show, dim or hide the fog overlays depending on what the player can see
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn update_overlays(
+	fog: Res<Fog>,
+	visible: Res<Visible>,
+	explored: Res<Explored>,
+	added: Query<(), Added<FogOverlay>>,
+	mut overlays: Query<(&FogOverlay, &mut Handle<StandardMaterial>, &mut Visibility)>,
+) {
+	if !(fog.is_changed() || visible.is_changed() || explored.is_changed() || !added.is_empty()) {
+		return;
+	}
+
+	for (&FogOverlay(pos), mut material, mut visibility) in &mut overlays {
+		if !fog.enabled || visible.contains(pos) {
+			*visibility = Visibility::Hidden;
+		} else if explored.contains(pos) {
+			*visibility = Visibility::Inherited;
+			*material = fog.remembered_material.clone();
+		} else {
+			*visibility = Visibility::Inherited;
+			*material = fog.hidden_material.clone();
+		}
+	}
+}
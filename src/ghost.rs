@@ -0,0 +1,314 @@
+//! Timing runs from the start to the exit, keeping the best run of every maze,
+//! and racing against a ghost replaying the best run.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	algorithms::MazeParams,
+	maze::{nearest_tile, MazeRegenerated, TilePos},
+	player::{AnimationTimer, Movement, Player, PlayerSprites},
+	storage,
+};
+
+/// The name of the file the best runs are saved in
+const FILE_NAME: &str = "best_runs.ron";
+/// The largest number of best runs which are kept (and saved)
+const MAX_BEST_RUNS: usize = 32;
+/// The number of movement steps between two recorded positions of a run
+const SAMPLE_STEPS: u32 = 4;
+/// The color the ghost's sprite is tinted with
+const GHOST_COLOR: Color = Color::rgba(0.7, 0.85, 1.0, 0.4);
+
+/// The fastest run through a maze
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BestRun {
+	/// The seed the maze was generated with
+	pub seed: u64,
+	/// The parameters the maze was generated with
+	pub params: MazeParams,
+	/// The time it took to reach the exit
+	pub time: Duration,
+	/// The time between two recorded positions
+	pub interval: Duration,
+	/// The player's position over the course of the run
+	pub positions: Vec<Vec2>,
+}
+
+/// The best runs of the [`MAX_BEST_RUNS`] mazes with the most recent new best
+/// runs, from the oldest to the newest
+#[derive(Debug, Clone, Default, Resource, Serialize, Deserialize)]
+pub struct BestRuns(Vec<BestRun>);
+
+impl BestRuns {
+	/// Get the best run of the maze generated with `seed` and `params`
+	pub fn get(&self, seed: u64, params: MazeParams) -> Option<&BestRun> {
+		self.0
+			.iter()
+			.find(|run| run.seed == seed && run.params == params)
+	}
+
+	/// Keep `run` if it is faster than the best run of the same maze, returning
+	/// whether it was
+	///
+	/// If there are more than [`MAX_BEST_RUNS`] runs afterwards, the oldest
+	/// ones are removed.
+	fn insert(&mut self, run: BestRun) -> bool {
+		if let Some(i) = self
+			.0
+			.iter()
+			.position(|best| best.seed == run.seed && best.params == run.params)
+		{
+			if self.0[i].time <= run.time {
+				return false;
+			}
+
+			self.0.remove(i);
+		}
+
+		self.0.push(run);
+		self.truncate();
+		true
+	}
+
+	/// Remove the oldest runs, keeping at most [`MAX_BEST_RUNS`]
+	fn truncate(&mut self) {
+		let excess = self.0.len().saturating_sub(MAX_BEST_RUNS);
+		self.0.drain(..excess);
+	}
+
+	fn load() -> Self {
+		let Some(saved) = storage::load_config(FILE_NAME) else {
+			return Self::default();
+		};
+
+		let mut runs: Self = ron::from_str(&saved).unwrap_or_else(|e| {
+			warn!("invalid saved best runs: {e}");
+			Self::default()
+		});
+
+		runs.truncate();
+		runs
+	}
+
+	fn save(&self) {
+		match ron::to_string(self) {
+			Ok(saved) => storage::save_config(FILE_NAME, &saved),
+			Err(e) => warn!("could not serialize best runs: {e}"),
+		}
+	}
+}
+
+/// A run through the current maze
+#[derive(Debug, Clone)]
+pub struct Run {
+	seed: u64,
+	params: MazeParams,
+	exit: TilePos,
+	steps: u32,
+	positions: Vec<Vec2>,
+	/// The time since the start of the run
+	pub elapsed: Duration,
+	/// Whether the player has reached the exit
+	pub finished: bool,
+}
+
+impl Run {
+	const fn new(event: &MazeRegenerated) -> Self {
+		Self {
+			seed: event.seed,
+			params: event.params,
+			exit: event.exit,
+			steps: 0,
+			positions: Vec::new(),
+			elapsed: Duration::ZERO,
+			finished: false,
+		}
+	}
+
+	/// Get the number of movement steps taken during this run
+	pub const fn steps(&self) -> u32 {
+		self.steps
+	}
+
+	/// Get the time of the best run of this run's maze
+	pub fn best_time(&self, best: &BestRuns) -> Option<Duration> {
+		best.get(self.seed, self.params).map(|run| run.time)
+	}
+}
+
+/// The run through the current maze, once the first maze was generated
+#[derive(Debug, Clone, Default, Resource)]
+pub struct CurrentRun(pub Option<Run>);
+
+/// Whether the ghost of the best run is shown
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct GhostSettings {
+	pub enabled: bool,
+}
+
+/// A translucent copy of the player replaying the positions of a best run
+#[derive(Debug, Clone, Component)]
+pub struct Ghost {
+	interval: Duration,
+	positions: Vec<Vec2>,
+}
+
+pub fn initialize(mut commands: Commands) {
+	commands.insert_resource(BestRuns::load());
+	commands.insert_resource(CurrentRun::default());
+	commands.insert_resource(GhostSettings { enabled: true });
+}
+
+/// Start a new run when the maze is regenerated, and (re)spawn the ghost of the
+/// maze's best run when the maze is regenerated or the ghost is turned on
+#[allow(clippy::type_complexity)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn start(
+	mut commands: Commands,
+	mut regenerated: EventReader<MazeRegenerated>,
+	mut run: ResMut<CurrentRun>,
+	settings: Res<GhostSettings>,
+	best: Res<BestRuns>,
+	ghosts: Query<Entity, With<Ghost>>,
+	players: Query<(
+		&Player,
+		&PlayerSprites,
+		&Transform,
+		&TextureAtlas,
+		&Handle<Image>,
+	)>,
+) {
+	if let Some(event) = regenerated.read().last() {
+		run.0 = Some(Run::new(event));
+	} else if !settings.is_changed() {
+		return;
+	}
+
+	for ghost in &ghosts {
+		commands.entity(ghost).despawn_recursive();
+	}
+
+	if !settings.enabled {
+		return;
+	}
+
+	let Some(run) = &run.0 else {
+		return;
+	};
+
+	let Some(best) = best.get(run.seed, run.params) else {
+		return;
+	};
+
+	let player = players.iter().find(|(p, ..)| p.is_main());
+
+	let (Some(&start), Some((_, sprites, transform, atlas, texture))) =
+		(best.positions.first(), player)
+	else {
+		return;
+	};
+
+	commands.spawn((
+		Ghost {
+			interval: best.interval,
+			positions: best.positions.clone(),
+		},
+		sprites.clone(),
+		Movement::new(start),
+		AnimationTimer::new(),
+		SpriteSheetBundle {
+			atlas: atlas.clone(),
+			texture: texture.clone(),
+			sprite: Sprite {
+				color: GHOST_COLOR,
+				..default()
+			},
+			transform: Transform {
+				translation: start.extend(transform.translation.z - 0.5),
+				scale: transform.scale,
+				..default()
+			},
+			..default()
+		},
+	));
+}
+
+/// Time the current run, record the player's positions and finish the run once
+/// the player reaches the exit, keeping it if it is the maze's best run
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn track(
+	time: Res<Time>,
+	mut run: ResMut<CurrentRun>,
+	mut best: ResMut<BestRuns>,
+	players: Query<(&Player, &Transform)>,
+) {
+	let player = players.iter().find(|(p, _)| p.is_main());
+
+	let (Some(run), Some((_, player))) = (&mut run.0, player) else {
+		return;
+	};
+
+	if run.finished {
+		return;
+	}
+
+	let position = player.translation.truncate();
+
+	if run.steps % SAMPLE_STEPS == 0 {
+		run.positions.push(position);
+	}
+
+	run.steps += 1;
+	run.elapsed += time.delta();
+
+	if nearest_tile(position) != run.exit {
+		return;
+	}
+
+	run.finished = true;
+	run.positions.push(position);
+
+	let time = run.elapsed;
+	let is_best = best.insert(BestRun {
+		seed: run.seed,
+		params: run.params,
+		time,
+		interval: time.mul_f64(f64::from(SAMPLE_STEPS) / f64::from(run.steps)),
+		positions: run.positions.clone(),
+	});
+
+	if is_best {
+		info!("reached the exit in {:.2}s (new best)", time.as_secs_f32());
+		best.save();
+	} else {
+		info!("reached the exit in {:.2}s", time.as_secs_f32());
+	}
+}
+
+/// Move the ghost to the best run's position at the current run's time
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn update(run: Res<CurrentRun>, mut ghosts: Query<(&Ghost, &mut Transform, &mut Movement)>) {
+	let Some(run) = &run.0 else {
+		return;
+	};
+
+	for (ghost, mut transform, mut movement) in &mut ghosts {
+		let progress = run.elapsed.as_secs_f32() / ghost.interval.as_secs_f32();
+		#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+		let index = progress.floor() as usize;
+
+		let (from, to) = match (ghost.positions.get(index), ghost.positions.get(index + 1)) {
+			(Some(&from), Some(&to)) => (from, to),
+			(Some(&last), None) => (last, last),
+			_ => continue,
+		};
+
+		let position = from.lerp(to, progress.fract());
+		transform.translation.x = position.x;
+		transform.translation.y = position.y;
+		movement.set_velocity((to - from) / ghost.interval.as_secs_f32());
+	}
+}
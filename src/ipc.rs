@@ -0,0 +1,71 @@
+//! The events socket on native, through which a launcher embedding the game
+//! receives its events and controls it.
+//!
+//! With the `--events-socket <path>` command line option, the game connects to
+//! the Unix domain socket at `path` (which the launcher listens on), writes its
+//! events there as JSON lines (see [`events`](crate::events)) and reads
+//! commands from it as JSON lines (see [`Command`]), like
+//! `{"command":"regenerate"}` or `{"command":"quit"}`.
+
+use std::{
+	io::{BufRead, BufReader},
+	os::unix::net::UnixStream,
+	thread,
+};
+
+use bevy::prelude::*;
+
+use crate::{commands::Command, events};
+
+/// Connect to the events socket given on the command line, if any, and read
+/// commands from it in the background
+///
+/// This is called before logging is set up, so errors are printed to stderr.
+pub fn connect() -> Option<UnixStream> {
+	let path = events::arg("--events-socket")?;
+
+	let socket = UnixStream::connect(&path)
+		.map_err(|e| eprintln!("could not connect to the events socket {path:?}: {e}"))
+		.ok()?;
+
+	let reader = socket
+		.try_clone()
+		.map_err(|e| eprintln!("could not read from the events socket: {e}"))
+		.ok()?;
+
+	thread::Builder::new()
+		.name("events socket".to_string())
+		.spawn(move || read(reader))
+		.map_err(|e| eprintln!("could not read from the events socket: {e}"))
+		.ok()?;
+
+	Some(socket)
+}
+
+/// Read commands from the events socket until it is closed
+fn read(socket: UnixStream) {
+	for line in BufReader::new(socket).lines() {
+		let line = match line {
+			Ok(line) => line,
+			Err(e) => {
+				warn!("could not read from the events socket: {e}");
+				return;
+			}
+		};
+
+		if line.trim().is_empty() {
+			continue;
+		}
+
+		let command = serde_json::from_str::<Command>(&line)
+			.map_err(|e| e.to_string())
+			.and_then(Command::validate);
+
+		match command {
+			Ok(command) => command.send(),
+			Err(e) => warn!("invalid command {line:?}: {e}"),
+		}
+	}
+
+	info!("the events socket was closed");
+}
@@ -0,0 +1,336 @@
+//! Localization of the UI's text, with a catalog of messages for every
+//! supported language.
+//!
+//! The catalogs (`assets/locales/*.ftl`) use a subset of the
+//! [Fluent](https://projectfluent.org/) syntax: single-line messages
+//! (`id = text`) with variables (`{ $name }`), and comments starting with `#`.
+//! They are built into the program, so that the text is available right away.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_simple_text_input::TextInputTextStyle;
+use serde::{Deserialize, Serialize};
+
+/// A language the UI is available in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+	English,
+	Polish,
+}
+
+impl Language {
+	pub const ALL: [Self; 2] = [Self::English, Self::Polish];
+
+	/// The source of the language's catalog
+	const fn source(self) -> &'static str {
+		match self {
+			Self::English => include_str!("../assets/locales/en.ftl"),
+			Self::Polish => include_str!("../assets/locales/pl.ftl"),
+		}
+	}
+
+	/// The language's code, as in `navigator.language` or `LANG`
+	const fn code(self) -> &'static str {
+		match self {
+			Self::English => "en",
+			Self::Polish => "pl",
+		}
+	}
+
+	/// The font used for the language's text
+	///
+	/// The pixel font only has ASCII characters, so languages with other
+	/// characters use a fallback font.
+	pub const fn font(self) -> &'static str {
+		match self {
+			Self::English => "fonts/pixel.ttf",
+			Self::Polish => "fonts/dejavu-sans-mono-bold.ttf",
+		}
+	}
+
+	/// The language after this one, going back to the first one after the last
+	pub const fn next(self) -> Self {
+		match self {
+			Self::English => Self::Polish,
+			Self::Polish => Self::English,
+		}
+	}
+
+	/// Find the language of a language tag or locale name (like `pl-PL` or
+	/// `pl_PL.UTF-8`)
+	fn from_tag(tag: &str) -> Option<Self> {
+		let code = tag.split(['-', '_', '.']).next()?.to_lowercase();
+		Self::ALL
+			.into_iter()
+			.find(|language| language.code() == code)
+	}
+
+	/// Detect the user's preferred language from the browser's
+	/// `navigator.language`, falling back to Polish
+	#[cfg(target_arch = "wasm32")]
+	pub fn detect() -> Self {
+		web_sys::window()
+			.and_then(|window| window.navigator().language())
+			.and_then(|tag| Self::from_tag(&tag))
+			.unwrap_or(Self::Polish)
+	}
+
+	/// Detect the user's preferred language from the `LC_ALL`, `LC_MESSAGES`
+	/// and `LANG` environment variables, falling back to Polish
+	#[cfg(not(target_arch = "wasm32"))]
+	pub fn detect() -> Self {
+		["LC_ALL", "LC_MESSAGES", "LANG"]
+			.into_iter()
+			.filter_map(|name| std::env::var(name).ok())
+			.find_map(|tag| Self::from_tag(&tag))
+			.unwrap_or(Self::Polish)
+	}
+}
+
+/// Parse the messages of a catalog
+fn parse(source: &'static str) -> HashMap<&'static str, &'static str> {
+	source
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.filter_map(|line| {
+			let Some((id, text)) = line.split_once('=') else {
+				warn!("invalid message: {line}");
+				return None;
+			};
+
+			Some((id.trim(), text.trim()))
+		})
+		.collect()
+}
+
+/// The messages of the current language
+#[derive(Debug, Clone, Resource)]
+pub struct Locale {
+	language: Language,
+	messages: HashMap<&'static str, &'static str>,
+	/// The messages used when the current language is missing one
+	fallback: HashMap<&'static str, &'static str>,
+}
+
+impl Locale {
+	pub fn new(language: Language) -> Self {
+		Self {
+			language,
+			messages: parse(language.source()),
+			fallback: parse(Language::English.source()),
+		}
+	}
+
+	pub const fn language(&self) -> Language {
+		self.language
+	}
+
+	/// The font used for the current language's text
+	pub const fn font(&self) -> &'static str {
+		self.language.font()
+	}
+
+	/// Get the message `id`, with the given values of its variables
+	///
+	/// Missing messages are replaced with their English version, or with their
+	/// id if that is missing too.
+	pub fn format(&self, id: &str, args: &[(&str, &str)]) -> String {
+		let Some(message) = self.messages.get(id).or_else(|| self.fallback.get(id)) else {
+			warn!("missing message: {id}");
+			return id.to_string();
+		};
+
+		let mut text = String::with_capacity(message.len());
+		let mut rest = *message;
+
+		while let Some((before, after)) = rest.split_once('{') {
+			text.push_str(before);
+
+			let Some((placeable, after)) = after.split_once('}') else {
+				text.push('{');
+				rest = after;
+				break;
+			};
+
+			let name = placeable.trim().trim_start_matches('$');
+			match args.iter().find(|(arg, _)| *arg == name) {
+				Some((_, value)) => text.push_str(value),
+				None => text.push_str(name),
+			}
+
+			rest = after;
+		}
+
+		text.push_str(rest);
+		text
+	}
+
+	/// Get the message `id`
+	pub fn get(&self, id: &str) -> String {
+		self.format(id, &[])
+	}
+}
+
+/// A text showing a message, which is translated again whenever the language
+/// changes
+#[derive(Debug, Clone, Component)]
+pub struct Localized {
+	id: &'static str,
+	args: Vec<(&'static str, String)>,
+}
+
+impl Localized {
+	pub const fn new(id: &'static str) -> Self {
+		Self {
+			id,
+			args: Vec::new(),
+		}
+	}
+
+	/// Set the value of the variable `name`
+	pub fn with_arg(mut self, name: &'static str, value: impl ToString) -> Self {
+		self.args.push((name, value.to_string()));
+		self
+	}
+
+	fn text(&self, locale: &Locale) -> String {
+		let args = self
+			.args
+			.iter()
+			.map(|(name, value)| (*name, value.as_str()))
+			.collect::<Vec<_>>();
+
+		locale.format(self.id, &args)
+	}
+}
+
+/// Create a text showing the message, which is translated again whenever the
+/// language changes
+pub fn text(locale: &Locale, message: Localized, style: TextStyle) -> (TextBundle, Localized) {
+	(
+		TextBundle::from_section(message.text(locale), style),
+		message,
+	)
+}
+
+/// Translate all [`Localized`] texts and switch all text to the language's font
+/// when the language changes
+#[allow(clippy::type_complexity)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn translate(
+	locale: Res<Locale>,
+	asset_server: Res<AssetServer>,
+	mut localized: Query<(&mut Text, &Localized)>,
+	mut texts: Query<&mut Text, Without<Localized>>,
+	mut inputs: Query<&mut TextInputTextStyle>,
+) {
+	if !locale.is_changed() || locale.is_added() {
+		return;
+	}
+
+	for (mut text, message) in &mut localized {
+		text.sections[0].value = message.text(&locale);
+	}
+
+	// Only fonts of the UI are replaced, not e.g. the debug overlay's
+	let fonts = Language::ALL.map(|language| asset_server.load::<Font>(language.font()));
+	let font = asset_server.load(locale.font());
+
+	let sections = localized
+		.iter_mut()
+		.map(|(text, _)| text)
+		.chain(texts.iter_mut())
+		.flat_map(|text| text.into_inner().sections.iter_mut());
+
+	for section in sections {
+		if fonts.contains(&section.style.font) {
+			section.style.font = font.clone();
+		}
+	}
+
+	for mut style in &mut inputs {
+		if fonts.contains(&style.0.font) {
+			style.0.font = font.clone();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn locale(messages: &'static str, fallback: &'static str) -> Locale {
+		Locale {
+			language: Language::Polish,
+			messages: parse(messages),
+			fallback: parse(fallback),
+		}
+	}
+
+	#[test]
+	fn parses_messages() {
+		let messages = parse("# comment\n\n  greeting = Hello, { $name }!  \nbroken\nempty =\n");
+
+		assert_eq!(messages.len(), 2);
+		assert_eq!(messages["greeting"], "Hello, { $name }!");
+		assert_eq!(messages["empty"], "");
+	}
+
+	#[test]
+	fn formats_variables() {
+		let locale = locale("size = {$width}x{ $height } tiles", "");
+
+		assert_eq!(
+			locale.format("size", &[("width", "10"), ("height", "20")]),
+			"10x20 tiles"
+		);
+	}
+
+	#[test]
+	fn formats_missing_variable_as_its_name() {
+		let locale = locale("greeting = Hello, { $name }!", "");
+
+		assert_eq!(locale.get("greeting"), "Hello, name!");
+	}
+
+	#[test]
+	fn keeps_unterminated_placeable() {
+		let locale = locale("broken = a { $b } c { $d", "");
+
+		assert_eq!(locale.format("broken", &[("b", "B")]), "a B c { $d");
+	}
+
+	#[test]
+	fn falls_back_to_english() {
+		let locale = locale("both = Oba", "both = Both\nenglish = English only");
+
+		assert_eq!(locale.get("both"), "Oba");
+		assert_eq!(locale.get("english"), "English only");
+		assert_eq!(locale.get("missing"), "missing");
+	}
+
+	#[test]
+	fn finds_language_from_tag() {
+		assert_eq!(Language::from_tag("pl_PL.UTF-8"), Some(Language::Polish));
+		assert_eq!(Language::from_tag("pl-PL"), Some(Language::Polish));
+		assert_eq!(Language::from_tag("EN"), Some(Language::English));
+		assert_eq!(Language::from_tag("de_DE.UTF-8"), None);
+		assert_eq!(Language::from_tag("C"), None);
+	}
+
+	#[test]
+	fn catalogs_have_the_same_messages() {
+		let english = parse(Language::English.source());
+
+		for language in Language::ALL {
+			let mut ids = parse(language.source()).into_keys().collect::<Vec<_>>();
+			let mut expected = english.keys().copied().collect::<Vec<_>>();
+			ids.sort_unstable();
+			expected.sort_unstable();
+
+			assert_eq!(ids, expected, "{language:?}");
+		}
+	}
+}
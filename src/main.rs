@@ -14,7 +14,7 @@
 use std::alloc::System;
 use std::{
 	backtrace::{Backtrace, BacktraceStatus},
-	panic::PanicInfo,
+	panic::PanicHookInfo,
 };
 
 #[cfg(feature = "debug")]
@@ -22,7 +22,7 @@ use bevy::{
 	diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
 	log::Level,
 };
-use bevy::{log::LogPlugin, prelude::*, window::WindowMode};
+use bevy::{log::LogPlugin, prelude::*, ui::UiSystem, window::WindowMode};
 #[cfg(feature = "debug")]
 use bevy_debug_text_overlay::OverlayPlugin;
 use bevy_embedded_assets::{EmbeddedAssetPlugin, PluginMode};
@@ -43,8 +43,12 @@ use wasm_bindgen::prelude::*;
 #[cfg(feature = "debug")]
 use crate::util::TrackingAlloc;
 use crate::{
-	algorithms::MazeParams,
-	maze::RegenerateMaze,
+	locale::Locale,
+	maze::{MazeError, MazeRegenerated, RegenerateMaze},
+	player::MovementSettings,
+	settings::Settings,
+	state::AppState,
+	theme::{Theme, ThemeChanged, ThemeLoader},
 	util::{input, PlayerInput, Rand},
 };
 
@@ -72,15 +76,36 @@ pub static ALLOC: TrackingAlloc<SmallGlobalTlsf> = TrackingAlloc::new(SmallGloba
 pub static ALLOC: TrackingAlloc<System> = TrackingAlloc::new(System);
 
 mod algorithms;
+#[cfg(target_arch = "wasm32")]
+mod api;
+mod bindings;
 mod camera;
+mod collision;
+mod commands;
+mod crash;
 mod events;
+mod fog;
+mod ghost;
+#[cfg(unix)]
+mod ipc;
+mod locale;
 mod maze;
+mod minimap;
+mod multiplayer;
+mod navigation;
 mod path;
 mod player;
+mod presets;
+mod replay;
+mod settings;
+mod state;
+mod storage;
+mod theme;
+mod touch;
 mod ui;
 mod util;
 
-fn panic_hook(panic_info: &PanicInfo<'_>) {
+fn panic_hook(panic_info: &PanicHookInfo<'_>) {
 	#[cfg(target_arch = "wasm32")]
 	#[wasm_bindgen]
 	extern "C" {
@@ -114,11 +139,12 @@ fn panic_hook(panic_info: &PanicInfo<'_>) {
 	#[cfg(target_arch = "wasm32")]
 	web_sys::console::error_1(&JsValue::from_str(&msg));
 
+	crash::save(&msg);
 	events::panic(msg);
 }
 
 #[bevy_main]
-#[allow(clippy::missing_panics_doc)]
+#[allow(clippy::missing_panics_doc, clippy::too_many_lines)]
 pub fn main() {
 	std::panic::set_hook(Box::new(panic_hook));
 
@@ -140,12 +166,18 @@ pub fn main() {
 	events::init();
 	events::loaded("Maze");
 
+	let (settings, problems) = Settings::load();
 	let mut app = App::new();
 
 	let default_plugins = DefaultPlugins
 		.set(WindowPlugin {
 			primary_window: Some(Window {
-				mode: WindowMode::BorderlessFullscreen,
+				mode: if cfg!(target_arch = "wasm32") {
+					WindowMode::BorderlessFullscreen
+				} else {
+					settings.display.window_mode()
+				},
+				present_mode: settings.present_mode(),
 				resizable: true,
 				canvas: cfg!(target_arch = "wasm32").then(|| "#background".to_string()),
 				title: if cfg!(target_arch = "wasm32") {
@@ -160,13 +192,18 @@ pub fn main() {
 		.set(ImagePlugin::default_nearest())
 		.set(AssetPlugin::default())
 		.add_before::<AssetPlugin, _>(EmbeddedAssetPlugin {
-			mode: PluginMode::ReplaceDefault,
+			mode: PluginMode::ReplaceAndFallback {
+				path: "assets".to_string(),
+			},
 		})
 		.disable::<LogPlugin>();
 
 	app.insert_resource(ClearColor(Color::NONE))
 		.insert_resource(Rand::new())
-		.insert_resource(Msaa::Sample4)
+		.insert_resource(settings.antialiasing.msaa())
+		.insert_resource(GlobalVolume::new(settings.volume))
+		.insert_resource(settings.lights.directional_shadow_map())
+		.insert_resource(settings.lights.point_shadow_map())
 		.add_plugins((default_plugins, TextInputPlugin));
 
 	#[cfg(feature = "debug")]
@@ -187,47 +224,209 @@ pub fn main() {
 		util::LogMemoryUsagePlugin,
 	));
 
-	app.add_systems(PostStartup, events::initialized);
-	app.add_systems(Update, events::started);
+	for problem in problems {
+		warn!("{problem}");
+	}
+
+	app.add_systems(
+		Update,
+		events::started.run_if(not(in_state(AppState::Loading))),
+	);
+	app.add_systems(
+		Update,
+		(
+			events::generated.after(maze::regenerate),
+			events::exit_reached,
+			events::settings_changed,
+			crash::track.after(maze::regenerate),
+			ui::click_crash,
+		),
+	);
+
+	app.add_systems(Update, commands::execute.before(maze::regenerate));
+
+	#[cfg(target_arch = "wasm32")]
+	app.add_systems(Startup, api::initialize)
+		.add_systems(Update, api::snapshot.after(maze::regenerate));
+
+	app.init_state::<AppState>();
+
+	app.init_asset::<Theme>()
+		.register_asset_loader(ThemeLoader)
+		.add_event::<ThemeChanged>();
 
 	app.add_systems(
 		Startup,
 		(
+			theme::initialize,
+			bindings::initialize,
 			player::initialize,
 			maze::initialize,
 			camera::initialize,
 			path::initialize,
 			ui::initialize,
+			minimap::initialize,
+			fog::initialize,
+			navigation::initialize,
+			touch::initialize,
+			replay::initialize,
+			ghost::initialize,
+			multiplayer::initialize,
+			presets::initialize,
+			crash::initialize,
 		),
 	);
 
-	app.add_systems(PreUpdate, (input, ui::open_close));
+	app.add_systems(
+		PreUpdate,
+		(
+			bindings::capture,
+			input
+				.after(bindings::capture)
+				.run_if(not(replay::is_playing)),
+			touch::update.after(input).after(UiSystem::Focus),
+			navigation::follow
+				.after(touch::update)
+				.run_if(in_state(AppState::Playing)),
+			state::toggle_menu.after(touch::update),
+			ui::navigate.after(UiSystem::Focus),
+			minimap::toggle.after(touch::update),
+			fog::toggle.after(bindings::capture),
+			multiplayer::input.after(bindings::capture),
+		),
+	);
+
+	app.add_systems(
+		FixedUpdate,
+		(
+			replay::playback,
+			replay::record.after(replay::playback),
+			player::main_input.after(replay::record),
+			player::movement.after(player::main_input),
+			player::collision.after(player::movement),
+			ghost::track.after(player::collision),
+			ghost::update.after(ghost::track),
+			multiplayer::finish.after(player::collision),
+		)
+			.run_if(in_state(AppState::Playing)),
+	);
 
 	app.add_systems(
 		Update,
 		(
-			camera::movement,
 			player::animation,
 			player::light_flicker,
-			player::movement,
-			player::collision.after(player::movement),
 			path::flicker,
 			path::movement,
 			path::fadeout,
 			path::spawn_more,
+			navigation::select,
+			navigation::preview.after(navigation::select),
+			camera::zoom,
+			replay::toggle,
+			state::win,
+		)
+			.run_if(in_state(AppState::Playing)),
+	);
+
+	app.add_systems(Update, state::loaded.run_if(in_state(AppState::Loading)));
+	app.add_systems(OnExit(AppState::Loading), events::initialized);
+	app.add_systems(Update, state::next_maze.run_if(in_state(AppState::Won)));
+	app.add_systems(
+		OnEnter(AppState::Menu),
+		(ui::open, ui::open_crash_dialog, events::menu_opened),
+	);
+	app.add_systems(OnExit(AppState::Menu), (ui::close, events::menu_closed));
+	app.add_systems(OnEnter(AppState::Paused), (ui::open, events::menu_opened));
+	app.add_systems(OnExit(AppState::Paused), (ui::close, events::menu_closed));
+	app.add_systems(OnEnter(AppState::Won), ui::open_win_screen);
+	app.add_systems(OnExit(AppState::Won), ui::close_win_screen);
+
+	app.add_systems(
+		Update,
+		(
+			camera::movement,
 			maze::regenerate,
 			maze::spawn_visible_tiles,
 			maze::despawn_invisible_tiles,
 			ui::focus,
 			ui::click,
 			ui::select,
+			ui::click_settings,
 			ui::update,
+			ui::theme_name,
+		),
+	);
+
+	app.add_systems(
+		Update,
+		(
+			player::respawn.after(maze::regenerate),
+			camera::respawn.after(maze::regenerate),
+			ui::loading,
+			ui::stamina,
+			ui::bindings,
+			ui::settings,
+			ui::replay,
+			bindings::save,
+			replay::start.after(maze::regenerate),
+			replay::timestep.after(replay::toggle).after(replay::start),
+			ghost::start.after(maze::regenerate).after(player::respawn),
+			ui::run_time,
+			ui::ghost,
+			ui::errors,
+		),
+	);
+
+	app.add_systems(
+		Update,
+		(
+			multiplayer::spawn,
+			multiplayer::start.after(maze::regenerate),
+			ui::players,
+			ui::focus_ring,
+			ui::validation.after(ui::update),
+			settings::apply,
+			settings::save,
+			locale::translate,
+			ui::click_presets,
+			ui::preset_name,
+			ui::show_params.after(ui::update),
+			presets::save,
+		),
+	);
+
+	app.add_systems(
+		Update,
+		(
+			theme::select,
+			maze::apply_theme.after(theme::select),
+			maze::update_tile_textures.after(maze::apply_theme),
+			player::apply_theme.after(theme::select),
+			camera::apply_theme.after(theme::select),
+		),
+	);
+
+	app.add_systems(
+		Update,
+		(
+			fog::update_line_of_sight,
+			fog::spawn_overlays,
+			fog::update_overlays
+				.after(fog::update_line_of_sight)
+				.after(fog::spawn_overlays),
+			minimap::draw.after(fog::update_line_of_sight),
 		),
 	);
 
 	app.insert_resource(PlayerInput::default());
-	app.insert_resource(MazeParams::default());
+	app.insert_resource(MovementSettings::default());
+	app.insert_resource(settings.maze);
+	app.insert_resource(Locale::new(settings.language));
+	app.insert_resource(settings);
 	app.add_event::<RegenerateMaze>();
+	app.add_event::<MazeRegenerated>();
+	app.add_event::<MazeError>();
 
 	app.run();
 }
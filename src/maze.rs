@@ -1,9 +1,11 @@
 use std::{
 	array,
+	error::Error,
 	f32::consts::PI,
-	fmt::{Debug, Formatter, Result as FmtResult},
+	fmt::{Debug, Display, Formatter, Result as FmtResult},
 	iter,
 	ops::Neg,
+	time::Duration,
 };
 
 use bevy::{
@@ -11,15 +13,19 @@ use bevy::{
 	render::render_resource::{
 		Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
 	},
+	tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+	utils::{HashMap, Instant},
 	window::PrimaryWindow,
 };
-use image::{imageops, load_from_memory, RgbaImage};
+use image::{imageops, RgbaImage};
+use turborand::{rng::Rng, SeededCore};
 
 use self::Direction::{Bottom, Left, Right, Top};
 use super::algorithms::{gen_maze, MazeParams};
 use crate::{
-	algorithms::{gen_rooms, solve_maze, SortedTree},
+	algorithms::{gen_rooms, solve_maze, SortedTree, Tree},
 	path::{self, Path},
+	theme::{Theme, ThemeChanged},
 	util::{Rand, TurboRand},
 };
 
@@ -31,12 +37,20 @@ pub const TILE_SIZE: Vec2 = Vec2::new(32.0, 32.0);
 pub const TILE_SCALE: f32 = 5.0;
 pub const WALL_THICKNESS: f32 = 4.0;
 
+/// The tile in which the player starts and in which maze generation begins
+pub const START: TilePos = TilePos {
+	x: MAZE_SIZE.x / 2,
+	y: MAZE_SIZE.y / 2,
+};
+
 pub const SUBTILE_SIZE: Vec2 = Vec2::new(16.0, 16.0);
 pub const SUBTILE_SCALE: f32 = 2.0 / 5.0;
 
 #[derive(Resource)]
 pub struct Maze {
 	pub tiles: Box<[Tile]>,
+	/// The parameters this maze was generated with
+	pub params: MazeParams,
 	textures: Box<[Handle<StandardMaterial>; 256]>,
 	wall_mesh: Handle<Mesh>,
 	floor_mesh: Handle<Mesh>,
@@ -44,10 +58,7 @@ pub struct Maze {
 }
 
 impl Maze {
-	/// Create a new `Maze`
-	///
-	/// # Panic
-	/// Panics if the maze is not `MAZE_SIZE` tiles large
+	/// Create a new `Maze`, which must be `MAZE_SIZE` tiles large
 	#[allow(clippy::too_many_arguments)]
 	pub fn new(
 		maze: impl Into<Box<[Tile]>>,
@@ -62,9 +73,9 @@ impl Maze {
 	) -> Self {
 		let tiles = maze.into();
 
-		assert_eq!(
-			MAZE_SIZE.x * MAZE_SIZE.y,
-			u32::try_from(tiles.len()).expect("maze is too large"),
+		debug_assert_eq!(
+			u32::try_from(tiles.len()).ok(),
+			Some(MAZE_SIZE.x * MAZE_SIZE.y),
 			"the maze's size is incorrect"
 		);
 
@@ -76,12 +87,12 @@ impl Maze {
 			material: roof_material,
 			transform: Transform {
 				translation: Vec3 {
-					x: if params.width % 2 == 0 {
+					x: if params.width.is_multiple_of(2) {
 						TILE_SIZE.x / 2.0 * TILE_SCALE
 					} else {
 						0.0
 					},
-					y: if params.height % 2 == 0 {
+					y: if params.height.is_multiple_of(2) {
 						TILE_SIZE.y / 2.0 * TILE_SCALE
 					} else {
 						0.0
@@ -96,6 +107,7 @@ impl Maze {
 
 		Self {
 			tiles,
+			params,
 			textures,
 			wall_mesh,
 			floor_mesh,
@@ -223,15 +235,13 @@ pub struct Tile(pub u8);
 impl Tile {
 	/// Fully closed stone tile
 	pub const CLOSED: Self = Self(0b1111_1111);
-	/// Fully open stone tile
-	pub const OPEN: Self = Self(0);
 
 	pub fn grass(rng: &Rand) -> Self {
 		Self(rng.u8(0..0xf) << 4 | 0b1111)
 	}
 
 	/// Open the given `side` of this Tile
-	pub fn open(&mut self, side: Direction) -> &mut Self {
+	pub const fn open(&mut self, side: Direction) -> &mut Self {
 		match side {
 			Direction::Top => self.0 &= 0b1111_0111,
 			Direction::Right => self.0 &= 0b1111_1011,
@@ -297,8 +307,134 @@ pub struct Roof;
 #[derive(Debug, Clone, Resource)]
 pub struct Paths(pub SortedTree<TilePos>);
 
+/// Regenerate the maze with the current [`MazeParams`]
+#[derive(Debug, Clone, Copy, Default, Event)]
+pub struct RegenerateMaze {
+	/// The seed to generate the maze with, or `None` for a random seed
+	pub seed: Option<u64>,
+}
+
+/// Statistics about a generated maze
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MazeStats {
+	/// The number of tiles on the shortest path from the start to the exit
+	pub solution_length: u32,
+	/// The number of tiles in the maze with only one open side
+	pub dead_ends: u32,
+}
+
+impl MazeStats {
+	/// Calculate the statistics of `maze` with the given `paths` to its exit
+	pub fn new(maze: &Maze, paths: &SortedTree<TilePos>) -> Self {
+		let solution_length = iter::successors(paths.search(&START), |&i| paths.parent(i))
+			.count()
+			.try_into()
+			.unwrap();
+
+		let params = maze.params;
+		let xs = params.margin_x()..params.margin_x() + params.width();
+		let dead_ends = (params.margin_y()..params.margin_y() + params.height())
+			.flat_map(|y| xs.clone().map(move |x| TilePos { x, y }))
+			.filter(|&pos| {
+				let tile = maze.get(pos);
+				[Top, Right, Bottom, Left]
+					.into_iter()
+					.filter(|&side| tile.is_open(side))
+					.count() == 1
+			})
+			.count()
+			.try_into()
+			.unwrap();
+
+		Self {
+			solution_length,
+			dead_ends,
+		}
+	}
+}
+
+/// The maze was regenerated and the player should start a new run
 #[derive(Debug, Clone, Copy, Event)]
-pub struct RegenerateMaze;
+pub struct MazeRegenerated {
+	/// The position of the new maze's exit
+	pub exit: TilePos,
+	/// The seed the new maze was generated with
+	pub seed: u64,
+	/// The parameters the new maze was generated with
+	pub params: MazeParams,
+	/// Statistics about the new maze
+	pub stats: MazeStats,
+	/// The time it took to generate the new maze's tiles
+	pub generation_time: Duration,
+}
+
+impl Display for MazeRegenerated {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		f.write_fmt(format_args!(
+			"new {}x{} maze (seed {:x}): exit at {:?}, {} tile solution, {} dead ends, generated \
+			 in {:.1}ms",
+			self.params.width,
+			self.params.height,
+			self.seed,
+			self.exit,
+			self.stats.solution_length,
+			self.stats.dead_ends,
+			self.generation_time.as_secs_f64() * 1000.0,
+		))
+	}
+}
+
+/// An error encountered while generating, solving or showing a maze
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+pub enum MazeError {
+	/// The roof covering the area outside of the maze is missing
+	NoRoof,
+	/// The tile the maze was solved from is not part of the maze
+	OutsideMaze(TilePos),
+	/// The theme with the given name or path could not be loaded
+	Theme(String),
+	/// The saved replay could not be loaded, for the given reason
+	Replay(String),
+}
+
+impl MazeError {
+	/// Log the error and send it to be shown in the UI
+	pub fn report(self, errors: &mut EventWriter<Self>) {
+		error!("{self}");
+		errors.send(self);
+	}
+}
+
+impl Display for MazeError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		match self {
+			Self::NoRoof => f.write_str("the maze has no roof"),
+			Self::OutsideMaze(pos) => {
+				f.write_fmt(format_args!("the tile {pos:?} is not part of the maze"))
+			}
+			Self::Theme(name) => f.write_fmt(format_args!("could not load the theme {name}")),
+			Self::Replay(reason) => {
+				f.write_fmt(format_args!("could not load the replay: {reason}"))
+			}
+		}
+	}
+}
+
+impl Error for MazeError {}
+
+/// Generate the tiles of a maze with the given `seed` and `params`, returning
+/// them along with the maze's exit
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+fn generate(seed: u64, params: MazeParams) -> (Vec<Tile>, TilePos) {
+	let rng = Rand::with_seed(seed);
+
+	let mut tiles = prepare_maze(&rng, params);
+	let exit = gen_maze(&mut tiles, &rng, params);
+	gen_rooms(&mut tiles, &rng, params);
+	adjust_maze_textures(&mut tiles, params);
+
+	(tiles, exit)
+}
 
 #[allow(clippy::type_complexity, clippy::too_many_arguments)]
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
@@ -310,22 +446,46 @@ pub fn regenerate(
 	params: Res<MazeParams>,
 	rng: Res<Rand>,
 	mut events: EventReader<RegenerateMaze>,
+	mut regenerated: EventWriter<MazeRegenerated>,
 	roof: Query<(Entity, &Handle<Mesh>, &Handle<StandardMaterial>), With<Roof>>,
 	mut paths: ResMut<Paths>,
+	mut errors: EventWriter<MazeError>,
 ) {
-	if !events.is_empty() {
-		events.clear();
+	if let Some(event) = events.read().last() {
+		let Ok((roof, roof_mesh, roof_material)) = roof.get_single() else {
+			MazeError::NoRoof.report(&mut errors);
+			return;
+		};
 
-		let mut new_tiles = prepare_maze(&rng, *params);
-		let start = gen_maze(&mut new_tiles, &rng, *params);
-		gen_rooms(&mut new_tiles, &rng, *params);
-		adjust_maze_textures(&mut new_tiles, *params);
+		let seed = event.seed.unwrap_or_else(|| rng.u64(..));
+		let start = Instant::now();
+		let (new_tiles, exit) = generate(seed, *params);
+		let generation_time = start.elapsed();
+
+		let old_tiles = std::mem::replace(&mut maze.tiles, new_tiles.into());
+		let old_params = std::mem::replace(&mut maze.params, *params);
+
+		// Keep the previous maze if the new one can not be solved
+		match solve_maze(&maze, exit, *params) {
+			Ok(solution) => paths.0 = solution,
+			Err(e) => {
+				maze.tiles = old_tiles;
+				maze.params = old_params;
+				e.report(&mut errors);
+				return;
+			}
+		}
 
-		maze.tiles = new_tiles.into();
-		info!("maze exit at {start:?}");
-		paths.0 = solve_maze(&maze, start, *params);
+		let event = MazeRegenerated {
+			exit,
+			seed,
+			params: *params,
+			stats: MazeStats::new(&maze, &paths.0),
+			generation_time,
+		};
 
-		let (roof, roof_mesh, roof_material) = roof.single();
+		info!("{event}");
+		regenerated.send(event);
 
 		let roof_size = UVec2::new(params.width() + 1, params.height() + 1);
 
@@ -335,12 +495,12 @@ pub fn regenerate(
 			material: roof_material.clone(),
 			transform: Transform {
 				translation: Vec3 {
-					x: if params.width % 2 == 0 {
+					x: if params.width.is_multiple_of(2) {
 						TILE_SIZE.x / 2.0 * TILE_SCALE
 					} else {
 						0.0
 					},
-					y: if params.height % 2 == 0 {
+					y: if params.height.is_multiple_of(2) {
 						TILE_SIZE.y / 2.0 * TILE_SCALE
 					} else {
 						0.0
@@ -367,96 +527,281 @@ pub fn regenerate(
 	}
 }
 
-#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
-fn gen_tile_textures(
-	wall: &[&[u8]],
-	floor: &[&[u8]],
-	grass: &[&[u8]],
-	images: &mut Assets<Image>,
-	rng: &Rand,
-) -> [Handle<Image>; 256] {
-	let mut res = array::from_fn::<_, 256, _>(|_| None);
-
-	let wall = wall
-		.iter()
-		.map(|data| load_from_memory(data).expect("invalid image").into_rgba8())
-		.collect::<Vec<_>>();
-
-	let grass = grass
-		.iter()
-		.map(|data| load_from_memory(data).expect("invalid image").into_rgba8())
-		.collect::<Vec<_>>();
+/// The sprite of a wall autotile sheet to use for one quarter of a wall
+/// subtile, given whether there are walls next to that quarter horizontally,
+/// vertically and diagonally
+///
+/// The sprites are (in order) an isolated wall, a vertical wall, a horizontal
+/// wall, a wall with inner corners and a wall surrounded by other walls
+const fn autotile_sprite(horizontal: bool, vertical: bool, diagonal: bool) -> u32 {
+	match (horizontal, vertical, diagonal) {
+		(false, false, _) => 0,
+		(false, true, _) => 1,
+		(true, false, _) => 2,
+		(true, true, false) => 3,
+		(true, true, true) => 4,
+	}
+}
 
-	let floor = floor
-		.iter()
-		.map(|data| load_from_memory(data).expect("invalid image").into_rgba8())
-		.collect::<Vec<_>>();
+/// Generate the texture for tiles with the given `bits`
+///
+/// If there are any `autotile` sheets, walls are drawn from them (on top of the
+/// floor) instead of from the `wall` images. The other image lists are never
+/// empty, since [`ThemeLoader`](crate::theme::ThemeLoader) rejects such themes.
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+fn gen_tile_texture(
+	bits: u8,
+	wall: &[RgbaImage],
+	autotile: &[RgbaImage],
+	floor: &[RgbaImage],
+	grass: &[RgbaImage],
+	rng: &impl TurboRand,
+) -> RgbaImage {
+	let tile = Tile(if bits & 0b1111 == 0b1111 {
+		bits
+	} else {
+		bits & 0b1111
+	});
 
-	for bits in 0u8..=255u8 {
-		let tile = Tile(if bits & 0b1111 == 0b1111 {
-			bits
-		} else {
-			bits & 0b1111
-		});
+	let is_edge = |sx, sy| match (sx, sy) {
+		(1..=3, 0) => tile.is_closed(Top),
+		(4, 1..=3) => tile.is_closed(Right),
+		(1..=3, 4) => tile.is_closed(Bottom),
+		(0, 1..=3) => tile.is_closed(Left),
+		(0, 0) => tile.is_closed(Top) || tile.is_closed(Left) || (bits & 0b1000_0000 != 0),
+		(4, 0) => tile.is_closed(Top) || tile.is_closed(Right) || (bits & 0b0100_0000 != 0),
+		(0, 4) => tile.is_closed(Bottom) || tile.is_closed(Left) || (bits & 0b0010_0000 != 0),
+		(4, 4) => tile.is_closed(Bottom) || tile.is_closed(Right) || (bits & 0b0001_0000 != 0),
+		_ => false,
+	};
 
-		let is_edge = |sx, sy| match (sx, sy) {
-			(1..=3, 0) => tile.is_closed(Top),
-			(4, 1..=3) => tile.is_closed(Right),
-			(1..=3, 4) => tile.is_closed(Bottom),
-			(0, 1..=3) => tile.is_closed(Left),
-			(0, 0) => tile.is_closed(Top) || tile.is_closed(Left) || (bits & 0b1000_0000 != 0),
-			(4, 0) => tile.is_closed(Top) || tile.is_closed(Right) || (bits & 0b0100_0000 != 0),
-			(0, 4) => tile.is_closed(Bottom) || tile.is_closed(Left) || (bits & 0b0010_0000 != 0),
-			(4, 4) => tile.is_closed(Bottom) || tile.is_closed(Right) || (bits & 0b0001_0000 != 0),
-			_ => false,
-		};
+	// Walls are shared with the neighbouring tiles, so they continue past the
+	// edges of this tile
+	let is_wall = |sx: i64, sy: i64| bits == 0xff || is_edge(sx.clamp(0, 4), sy.clamp(0, 4));
 
-		let is_fully_closed = tile.is_closed(Top)
-			&& tile.is_closed(Right)
-			&& tile.is_closed(Bottom)
-			&& tile.is_closed(Left);
+	let is_fully_closed = tile.is_closed(Top)
+		&& tile.is_closed(Right)
+		&& tile.is_closed(Bottom)
+		&& tile.is_closed(Left);
 
-		let mut image = RgbaImage::from_raw(5 * 16, 5 * 16, vec![0; 4 * 5 * 16 * 5 * 16]).unwrap();
+	let mut image = RgbaImage::from_raw(5 * 16, 5 * 16, vec![0; 4 * 5 * 16 * 5 * 16]).unwrap();
 
-		for sy in 0..5 {
-			for sx in 0..5 {
-				let subimage = if is_fully_closed && bits != 0xff {
-					rng.sample(&grass).expect("there are no grass images")
-				} else if is_edge(sx, sy) || bits == 0xff {
-					rng.sample(&wall).expect("there are no wall images")
-				} else {
-					rng.sample(&floor).expect("there are no floor images")
-				};
+	for sy in 0..5 {
+		for sx in 0..5 {
+			if is_fully_closed && bits != 0xff {
+				if let Some(subimage) = rng.sample(grass) {
+					imageops::overlay(&mut image, subimage, sx * 16, sy * 16);
+				}
+			} else if !is_wall(sx, sy) {
+				if let Some(subimage) = rng.sample(floor) {
+					imageops::overlay(&mut image, subimage, sx * 16, sy * 16);
+				}
+			} else if let Some(sheet) = rng.sample(autotile) {
+				if let Some(subimage) = rng.sample(floor) {
+					imageops::overlay(&mut image, subimage, sx * 16, sy * 16);
+				}
 
+				for (qx, qy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+					let (dx, dy) = (qx * 2 - 1, qy * 2 - 1);
+					let sprite = autotile_sprite(
+						is_wall(sx + dx, sy),
+						is_wall(sx, sy + dy),
+						is_wall(sx + dx, sy + dy),
+					);
+
+					let quarter = imageops::crop_imm(
+						sheet,
+						sprite * 16 + u32::try_from(qx).unwrap() * 8,
+						u32::try_from(qy).unwrap() * 8,
+						8,
+						8,
+					);
+
+					imageops::overlay(&mut image, &*quarter, sx * 16 + qx * 8, sy * 16 + qy * 8);
+				}
+			} else if let Some(subimage) = rng.sample(wall) {
 				imageops::overlay(&mut image, subimage, sx * 16, sy * 16);
 			}
 		}
+	}
 
-		let handle = images.add(Image {
-			data: image.into_vec(),
-			texture_descriptor: TextureDescriptor {
-				label: None,
-				size: Extent3d {
-					width: 5 * 16,
-					height: 5 * 16,
-					..default()
-				},
-				dimension: TextureDimension::D2,
-				format: TextureFormat::Rgba8UnormSrgb,
-				mip_level_count: 1,
-				sample_count: 1,
-				usage: TextureUsages::TEXTURE_BINDING
-					| TextureUsages::COPY_DST
-					| TextureUsages::RENDER_ATTACHMENT,
-				view_formats: &[],
+	image
+}
+
+/// Convert a generated tile texture into a Bevy `Image`
+fn tile_image(image: RgbaImage) -> Image {
+	Image {
+		data: image.into_vec(),
+		texture_descriptor: TextureDescriptor {
+			label: None,
+			size: Extent3d {
+				width: 5 * 16,
+				height: 5 * 16,
+				..default()
 			},
-			texture_view_descriptor: None,
-			..default()
-		});
-		res[bits as usize] = Some(handle);
+			dimension: TextureDimension::D2,
+			format: TextureFormat::Rgba8UnormSrgb,
+			mip_level_count: 1,
+			sample_count: 1,
+			usage: TextureUsages::TEXTURE_BINDING
+				| TextureUsages::COPY_DST
+				| TextureUsages::RENDER_ATTACHMENT,
+			view_formats: &[],
+		},
+		texture_view_descriptor: None,
+		..default()
+	}
+}
+
+/// The key of a cached tile texture: the tile's bits and the theme
+///
+/// The seed is not part of the key, since it is the same for the whole
+/// lifetime of the cache
+type TileTextureKey = (u8, AssetId<Theme>);
+
+/// Lazily generated tile textures
+///
+/// Only the textures for bit patterns which occur in the maze are generated
+/// (on the [`AsyncComputeTaskPool`]), and generated textures are kept around
+/// for later mazes and theme changes
+#[derive(Debug, Resource)]
+pub struct TileTextures {
+	/// The seed used for generating textures, so that a tile's texture is the
+	/// same whenever it is generated
+	seed: u64,
+	/// The theme for which textures are currently needed
+	theme: Option<AssetId<Theme>>,
+	/// Whether the set of needed textures may have changed
+	dirty: bool,
+	cache: HashMap<TileTextureKey, Handle<Image>>,
+	tasks: HashMap<TileTextureKey, Task<RgbaImage>>,
+}
+
+impl TileTextures {
+	/// Create a new empty cache using the given seed
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			theme: None,
+			dirty: false,
+			cache: HashMap::new(),
+			tasks: HashMap::new(),
+		}
+	}
+
+	/// Whether any textures are currently being generated
+	pub fn is_loading(&self) -> bool {
+		!self.tasks.is_empty()
+	}
+
+	/// Whether the textures of the current theme were generated, so that the
+	/// maze can be shown
+	pub fn is_ready(&self) -> bool {
+		self.theme.is_some() && !self.dirty && self.tasks.is_empty() && !self.cache.is_empty()
+	}
+
+	/// Remove the cached textures (and the ones being generated) of the given
+	/// `theme`, e.g. because it was modified
+	fn forget(&mut self, theme: AssetId<Theme>) {
+		self.cache.retain(|&(_, t), _| t != theme);
+		self.tasks.retain(|&(_, t), _| t != theme);
 	}
 
-	res.map(|o| o.expect("image creation failed"))
+	/// Get the cached texture for tiles with the given `bits` in the current
+	/// theme
+	fn get(&self, bits: u8) -> Option<Handle<Image>> {
+		self.cache.get(&(bits, self.theme?)).cloned()
+	}
+}
+
+/// Set the texture of a tile material, making it black while the texture is
+/// not yet available
+fn set_tile_texture(
+	material: &mut StandardMaterial,
+	theme: &Theme,
+	texture: Option<Handle<Image>>,
+) {
+	if texture.is_some() {
+		material.base_color = theme.tile_color;
+		material.emissive = theme.emissive;
+	} else {
+		material.base_color = Color::BLACK;
+		material.emissive = Color::BLACK;
+	}
+
+	material.base_color_texture.clone_from(&texture);
+	material.emissive_texture = texture;
+}
+
+/// Start generating the textures needed for the current maze and theme, and
+/// apply the ones which have finished generating
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn update_tile_textures(
+	maze: Res<Maze>,
+	themes: Res<Assets<Theme>>,
+	mut textures: ResMut<TileTextures>,
+	mut images: ResMut<Assets<Image>>,
+	mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+	let Some(theme_id) = textures.theme else {
+		return;
+	};
+
+	let Some(theme) = themes.get(theme_id) else {
+		return;
+	};
+
+	if maze.is_changed() || textures.dirty {
+		textures.dirty = false;
+
+		let mut needed = [false; 256];
+		for tile in &maze.tiles {
+			needed[tile.0 as usize] = true;
+		}
+
+		let pool = AsyncComputeTaskPool::get();
+		let seed = textures.seed;
+
+		for bits in (0u8..=255u8).filter(|&b| needed[b as usize]) {
+			let key = (bits, theme_id);
+
+			if textures.cache.contains_key(&key) || textures.tasks.contains_key(&key) {
+				continue;
+			}
+
+			let wall = theme.wall.clone();
+			let autotile = theme.wall_autotile.clone();
+			let floor = theme.floor.clone();
+			let grass = theme.outside.clone();
+
+			let task = pool.spawn(async move {
+				let rng =
+					Rng::with_seed(seed ^ u64::from(bits).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+				gen_tile_texture(bits, &wall, &autotile, &floor, &grass, &rng)
+			});
+
+			textures.tasks.insert(key, task);
+		}
+	}
+
+	let mut finished = Vec::new();
+
+	for (key, task) in &mut textures.tasks {
+		if let Some(image) = block_on(future::poll_once(task)) {
+			finished.push((*key, image));
+		}
+	}
+
+	for (key @ (bits, ..), image) in finished {
+		textures.tasks.remove(&key);
+
+		let handle = images.add(tile_image(image));
+		textures.cache.insert(key, handle.clone());
+
+		if let Some(material) = materials.get_mut(&maze.textures[bits as usize]) {
+			set_tile_texture(material, theme, Some(handle));
+		}
+	}
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
@@ -483,6 +828,41 @@ impl From<TilePos> for UVec2 {
 	}
 }
 
+/// A set of tile positions, stored as a bitset covering the whole world
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileSet(Box<[u64]>);
+
+impl TileSet {
+	/// Create a new empty `TileSet`
+	pub fn new() -> Self {
+		let len = usize::try_from(MAZE_SIZE.x * MAZE_SIZE.y).unwrap();
+		Self(vec![0; len.div_ceil(64)].into())
+	}
+
+	/// Whether the tile at `pos` is in this set
+	pub fn contains(&self, pos: TilePos) -> bool {
+		let i = usize::try_from(pos.index()).unwrap();
+		self.0[i / 64] & (1 << (i % 64)) != 0
+	}
+
+	/// Add the tile at `pos` to this set
+	pub fn insert(&mut self, pos: TilePos) {
+		let i = usize::try_from(pos.index()).unwrap();
+		self.0[i / 64] |= 1 << (i % 64);
+	}
+
+	/// Remove all tiles from this set
+	pub fn clear(&mut self) {
+		self.0.fill(0);
+	}
+}
+
+impl Default for TileSet {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
 pub fn initialize(
 	mut commands: Commands,
@@ -490,19 +870,9 @@ pub fn initialize(
 	params: Res<MazeParams>,
 	mut meshes: ResMut<Assets<Mesh>>,
 	mut materials: ResMut<Assets<StandardMaterial>>,
-	mut images: ResMut<Assets<Image>>,
+	mut regenerated: EventWriter<MazeRegenerated>,
+	mut errors: EventWriter<MazeError>,
 ) {
-	let wall = [&include_bytes!("../assets/maze/cave-wall.png")[..]];
-	let floor = [
-		&include_bytes!("../assets/maze/cave-floor-1.png")[..],
-		&include_bytes!("../assets/maze/cave-floor-2.png")[..],
-	];
-	let grass = [
-		&include_bytes!("../assets/maze/grass-1.png")[..],
-		&include_bytes!("../assets/maze/grass-2.png")[..],
-		&include_bytes!("../assets/maze/grass-3.png")[..],
-	];
-
 	let floor_mesh = meshes.add(Rectangle::from_size(TILE_SIZE));
 	let wall_mesh = meshes.add(Cuboid::new(
 		SUBTILE_SIZE.x.mul_add(SUBTILE_SCALE, TILE_SIZE.x),
@@ -529,19 +899,16 @@ pub fn initialize(
 		..default()
 	});
 
-	let mut maze = prepare_maze(&rng, *params);
-	let exit = gen_maze(&mut maze, &rng, *params);
-	gen_rooms(&mut maze, &rng, *params);
-	adjust_maze_textures(&mut maze, *params);
+	let seed = rng.u64(..);
+	let start = Instant::now();
+	let (maze, exit) = generate(seed, *params);
+	let generation_time = start.elapsed();
 
-	let textures = gen_tile_textures(&wall, &floor, &grass, &mut images, &rng).map(|h| {
+	// The textures are filled in by `update_tile_textures` once the theme is
+	// loaded
+	let textures = array::from_fn(|_| {
 		materials.add(StandardMaterial {
-			base_color: Color::GRAY,
-			base_color_texture: Some(h.clone()),
-			reflectance: rng.f32().mul_add(0.1, 0.1),
-			perceptual_roughness: rng.f32().mul_add(0.15, 0.85),
-			emissive: Color::hsl(210.0, 0.3, 0.3).as_rgba() * 18.0,
-			emissive_texture: Some(h),
+			base_color: Color::BLACK,
 			unlit: false,
 			..default()
 		})
@@ -559,10 +926,93 @@ pub fn initialize(
 		&mut commands,
 	);
 
-	commands.insert_resource(Paths(solve_maze(&maze, exit, *params)));
+	// There is no previous maze to fall back to, so the solution only contains
+	// the exit if the first maze can not be solved
+	let paths = solve_maze(&maze, exit, *params).unwrap_or_else(|e| {
+		e.report(&mut errors);
+		SortedTree::new(Tree::new(exit))
+	});
+
+	// Also send the event for the first maze, so that the first run starts just
+	// like the ones in regenerated mazes
+	let event = MazeRegenerated {
+		exit,
+		seed,
+		params: *params,
+		stats: MazeStats::new(&maze, &paths),
+		generation_time,
+	};
+
+	info!("{event}");
+	regenerated.send(event);
+
+	commands.insert_resource(Paths(paths));
+	commands.insert_resource(TileTextures::new(rng.u64(..)));
 	commands.insert_resource(maze);
 }
 
+/// Update the maze's materials and request the new tile textures when the theme
+/// changes
+///
+/// The cached textures of modified (e.g. hot-reloaded) themes are discarded, so
+/// that they are generated again from the new images
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn apply_theme(
+	mut events: EventReader<ThemeChanged>,
+	mut asset_events: EventReader<AssetEvent<Theme>>,
+	themes: Res<Assets<Theme>>,
+	maze: Res<Maze>,
+	rng: Res<Rand>,
+	roof: Query<&Handle<StandardMaterial>, With<Roof>>,
+	mut textures: ResMut<TileTextures>,
+	mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+	for event in asset_events.read() {
+		if let AssetEvent::Modified { id } = event {
+			textures.forget(*id);
+		}
+	}
+
+	let Some((id, theme)) = events
+		.read()
+		.last()
+		.and_then(|e| Some((e.0, themes.get(e.0)?)))
+	else {
+		return;
+	};
+
+	textures.theme = Some(id);
+	textures.dirty = true;
+
+	// Textures still being generated for a different theme are not needed
+	textures.tasks.retain(|&(_, theme), _| theme == id);
+
+	for (bits, material) in maze.textures.iter().enumerate() {
+		let Some(material) = materials.get_mut(material) else {
+			continue;
+		};
+
+		let roughness = &theme.roughness;
+		let reflectance = &theme.reflectance;
+
+		material.reflectance = rng
+			.f32()
+			.mul_add(reflectance.end - reflectance.start, reflectance.start);
+		material.perceptual_roughness = rng
+			.f32()
+			.mul_add(roughness.end - roughness.start, roughness.start);
+
+		set_tile_texture(material, theme, textures.get(bits.try_into().unwrap()));
+	}
+
+	for material in &roof {
+		if let Some(material) = materials.get_mut(material) {
+			material.base_color = theme.roof;
+		}
+	}
+}
+
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
 fn prepare_maze(rng: &Rand, params: MazeParams) -> Vec<Tile> {
 	let us = |u32: u32| -> usize { u32.try_into().unwrap() };
@@ -641,7 +1091,7 @@ pub fn tile_bits(i: usize, maze: &[Tile]) -> u8 {
 	);
 
 	let tile_is_edge = !(maze_size.0..=(maze_size.1 - 1) * maze_size.0).contains(&i)
-		|| i % maze_size.0 == 0
+		|| i.is_multiple_of(maze_size.0)
 		|| i % maze_size.0 == maze_size.0 - 1;
 
 	let mut res = tile.0 & 0b1111;
@@ -713,13 +1163,20 @@ pub fn spawn_visible_tiles(
 	maze: Res<Maze>,
 	tiles: Query<&TilePos, With<Tile>>,
 	window: Query<&Window, (With<PrimaryWindow>, Without<Tile>, Without<Camera2d>)>,
-	camera: Query<&Transform, (With<Camera2d>, Changed<Transform>, Without<Tile>)>,
+	camera: Query<
+		(&Transform, &OrthographicProjection),
+		(
+			With<Camera2d>,
+			Or<(Changed<Transform>, Changed<OrthographicProjection>)>,
+			Without<Tile>,
+		),
+	>,
 ) {
 	let Ok(window) = window.get_single() else {
 		return;
 	};
 
-	let Ok(camera) = camera.get_single() else {
+	let Ok((camera, projection)) = camera.get_single() else {
 		return;
 	};
 
@@ -728,8 +1185,12 @@ pub fn spawn_visible_tiles(
 	let new_tiles = (0..maze.tiles.len())
 		.filter(|&i| {
 			let Vec2 { x, y } = tile_position(i as u32);
-			let width = TILE_SIZE.x.mul_add(TILE_SCALE * 2.0, window.width());
-			let height = TILE_SIZE.y.mul_add(TILE_SCALE * 2.0, window.height());
+			let width = TILE_SIZE
+				.x
+				.mul_add(TILE_SCALE * 2.0, window.width() * projection.scale);
+			let height = TILE_SIZE
+				.y
+				.mul_add(TILE_SCALE * 2.0, window.height() * projection.scale);
 			let x_extent =
 				(camera.translation.x - width / 2.0)..(camera.translation.x + width / 2.0);
 			let y_extent =
@@ -756,20 +1217,31 @@ pub fn despawn_invisible_tiles(
 	mut commands: Commands,
 	tiles: Query<(Entity, &Transform), With<Tile>>,
 	window: Query<&Window, (With<PrimaryWindow>, Without<Tile>, Without<Camera2d>)>,
-	camera: Query<&Transform, (With<Camera2d>, Changed<Transform>, Without<Tile>)>,
+	camera: Query<
+		(&Transform, &OrthographicProjection),
+		(
+			With<Camera2d>,
+			Or<(Changed<Transform>, Changed<OrthographicProjection>)>,
+			Without<Tile>,
+		),
+	>,
 ) {
 	let Ok(window) = window.get_single() else {
 		return;
 	};
 
-	let Ok(camera) = camera.get_single() else {
+	let Ok((camera, projection)) = camera.get_single() else {
 		return;
 	};
 
 	let mut old_tiles = tiles.iter().filter(|&(_, t)| {
 		let Vec3 { x, y, .. } = t.translation;
-		let width = TILE_SIZE.x.mul_add(TILE_SCALE * 3.0, window.width());
-		let height = TILE_SIZE.y.mul_add(TILE_SCALE * 3.0, window.height());
+		let width = TILE_SIZE
+			.x
+			.mul_add(TILE_SCALE * 3.0, window.width() * projection.scale);
+		let height = TILE_SIZE
+			.y
+			.mul_add(TILE_SCALE * 3.0, window.height() * projection.scale);
 		let x_extent = (camera.translation.x - width / 2.0)..(camera.translation.x + width / 2.0);
 		let y_extent = (camera.translation.y - height / 2.0)..(camera.translation.y + height / 2.0);
 		!x_extent.contains(&x) || !y_extent.contains(&y)
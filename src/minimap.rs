@@ -0,0 +1,235 @@
+//! The minimap overlay and tracking of explored parts of the maze.
+
+use bevy::{
+	prelude::*,
+	render::{
+		render_asset::RenderAssetUsages,
+		render_resource::{Extent3d, TextureDimension, TextureFormat},
+	},
+};
+
+use crate::{
+	bindings::{Action, Actions},
+	maze::{
+		nearest_tile,
+		Direction::{Bottom, Left, Right, Top},
+		Maze, Paths, TilePos, TileSet,
+	},
+	player::Player,
+	touch::TouchControls,
+};
+
+/// The size (in pixels) of the block representing a single tile (the center
+/// pixel is the tile itself and the edges are its walls)
+const BLOCK_SIZE: u32 = 3;
+/// The height of the minimap as a percentage of the window's height
+const MINIMAP_HEIGHT: f32 = 30.0;
+
+const UNEXPLORED_COLOR: [u8; 4] = [0x00, 0x00, 0x00, 0x80];
+const WALL_COLOR: [u8; 4] = [0x68, 0x68, 0x78, 0xff];
+const FLOOR_COLOR: [u8; 4] = [0x18, 0x18, 0x24, 0xff];
+const SOLUTION_COLOR: [u8; 4] = [0x40, 0x80, 0xff, 0xff];
+const EXIT_COLOR: [u8; 4] = [0x40, 0xff, 0x60, 0xff];
+const PLAYER_COLOR: [u8; 4] = [0xff, 0xa0, 0x20, 0xff];
+
+/// The set of tiles which the player has seen (see
+/// [`update_line_of_sight`](crate::fog::update_line_of_sight))
+#[derive(Debug, Clone, Default, Resource, Deref, DerefMut)]
+pub struct Explored(pub TileSet);
+
+/// The minimap's state
+#[derive(Debug, Clone, Resource)]
+pub struct Minimap {
+	image: Handle<Image>,
+	/// Whether the path to the exit should be shown on the minimap
+	pub solution: bool,
+}
+
+/// Marker for the minimap's UI node
+#[derive(Debug, Clone, Copy, Component)]
+pub struct MinimapNode;
+
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn initialize(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+	let image = images.add(Image::new_fill(
+		Extent3d {
+			width: BLOCK_SIZE,
+			height: BLOCK_SIZE,
+			..default()
+		},
+		TextureDimension::D2,
+		&UNEXPLORED_COLOR,
+		TextureFormat::Rgba8UnormSrgb,
+		RenderAssetUsages::default(),
+	));
+
+	commands.spawn((MinimapNode, ImageBundle {
+		style: Style {
+			position_type: PositionType::Absolute,
+			right: Val::Vh(2.0),
+			bottom: Val::Vh(2.0),
+			height: Val::Vh(MINIMAP_HEIGHT),
+			..default()
+		},
+		image: UiImage::new(image.clone()),
+		visibility: Visibility::Hidden,
+		..default()
+	}));
+
+	commands.insert_resource(Minimap {
+		image,
+		solution: false,
+	});
+	commands.insert_resource(Explored::default());
+}
+
+/// Show or hide the minimap ([`Action::Overview`] or the touch button) and the
+/// solution on it ([`Action::Hint`])
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn toggle(
+	mut minimap: ResMut<Minimap>,
+	mut node: Query<&mut Visibility, With<MinimapNode>>,
+	actions: Actions,
+	touch: Res<TouchControls>,
+) {
+	if actions.just_pressed(Action::Overview) || touch.minimap {
+		for mut visibility in &mut node {
+			*visibility = match *visibility {
+				Visibility::Hidden => Visibility::Inherited,
+				_ => Visibility::Hidden,
+			};
+		}
+	}
+
+	if actions.just_pressed(Action::Hint) {
+		minimap.solution = !minimap.solution;
+	}
+}
+
+/// Redraw the minimap if anything shown on it has changed
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn draw(
+	minimap: Res<Minimap>,
+	explored: Res<Explored>,
+	maze: Res<Maze>,
+	paths: Res<Paths>,
+	players: Query<(&Player, &Transform)>,
+	mut node: Query<&mut Style, With<MinimapNode>>,
+	mut images: ResMut<Assets<Image>>,
+	mut last_players: Local<Vec<TilePos>>,
+) {
+	let mut players = players.iter().collect::<Vec<_>>();
+	players.sort_by_key(|(p, _)| p.0);
+	let players = players
+		.into_iter()
+		.map(|(_, t)| nearest_tile(t.translation.truncate()))
+		.collect::<Vec<_>>();
+
+	if !(minimap.is_changed()
+		|| explored.is_changed()
+		|| paths.is_changed()
+		|| players != *last_players)
+	{
+		return;
+	}
+
+	// The solution is shown from the main player's position
+	let player = players.first().copied();
+	*last_players = players;
+	let params = maze.params;
+
+	let Some(image) = images.get_mut(&minimap.image) else {
+		return;
+	};
+
+	let size = Extent3d {
+		width: params.width() * BLOCK_SIZE,
+		height: params.height() * BLOCK_SIZE,
+		..default()
+	};
+
+	if image.texture_descriptor.size != size {
+		image.resize(size);
+
+		#[allow(clippy::cast_precision_loss)]
+		for mut style in &mut node {
+			style.aspect_ratio = Some(size.width as f32 / size.height as f32);
+		}
+	}
+
+	let exit = paths.0.root().and_then(|i| paths.0.get(i)).copied();
+
+	let mut solution = Vec::new();
+	if minimap.solution {
+		let mut current = player.and_then(|p| paths.0.search(&p));
+		while let Some(i) = current {
+			solution.extend(paths.0.get(i).copied());
+			current = paths.0.parent(i);
+		}
+	}
+
+	let mut set_pixel = |x: u32, y: u32, color: [u8; 4]| {
+		let i = usize::try_from((y * size.width + x) * 4).unwrap();
+		image.data[i..i + 4].copy_from_slice(&color);
+	};
+
+	for x in 0..params.width() {
+		for y in 0..params.height() {
+			let pos = TilePos {
+				x: x + params.margin_x(),
+				y: y + params.margin_y(),
+			};
+
+			// The image's y axis points down, the world's points up
+			let (px, py) = (x * BLOCK_SIZE, (params.height() - 1 - y) * BLOCK_SIZE);
+
+			if !explored.contains(pos) {
+				for sx in 0..BLOCK_SIZE {
+					for sy in 0..BLOCK_SIZE {
+						set_pixel(px + sx, py + sy, UNEXPLORED_COLOR);
+					}
+				}
+
+				continue;
+			}
+
+			let tile = maze.get(pos);
+			let center = if last_players.contains(&pos) {
+				PLAYER_COLOR
+			} else if Some(pos) == exit {
+				EXIT_COLOR
+			} else if solution.contains(&pos) {
+				SOLUTION_COLOR
+			} else {
+				FLOOR_COLOR
+			};
+
+			let [top, right, bottom, left] = [Top, Right, Bottom, Left].map(|d| {
+				if tile.is_closed(d) {
+					WALL_COLOR
+				} else {
+					FLOOR_COLOR
+				}
+			});
+
+			let corner = |a: [u8; 4], b: [u8; 4]| {
+				if a == WALL_COLOR || b == WALL_COLOR {
+					WALL_COLOR
+				} else {
+					FLOOR_COLOR
+				}
+			};
+
+			set_pixel(px, py, corner(top, left));
+			set_pixel(px + 1, py, top);
+			set_pixel(px + 2, py, corner(top, right));
+			set_pixel(px, py + 1, left);
+			set_pixel(px + 1, py + 1, center);
+			set_pixel(px + 2, py + 1, right);
+			set_pixel(px, py + 2, corner(bottom, left));
+			set_pixel(px + 1, py + 2, bottom);
+			set_pixel(px + 2, py + 2, corner(bottom, right));
+		}
+	}
+}
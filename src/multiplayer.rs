@@ -0,0 +1,172 @@
+//! Local multiplayer: up to four players racing to the exit on a shared
+//! screen, each controlled by a gamepad or a half of the keyboard.
+
+use bevy::prelude::*;
+
+use crate::{
+	bindings::{Action, Actions, Device, KeyboardHalf},
+	maze::{nearest_tile, tile_position, MazeRegenerated, TilePos, START},
+	player::{self, MovementSettings, Player, PlayerSprites},
+	util::PlayerInput,
+};
+
+/// The largest number of local players
+pub const MAX_PLAYERS: usize = 4;
+
+/// The number of local players
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct Multiplayer {
+	pub players: usize,
+}
+
+impl Multiplayer {
+	/// Whether more than one player is playing
+	pub const fn is_enabled(self) -> bool {
+		self.players > 1
+	}
+
+	/// Change to the next number of players, going back to one player after
+	/// [`MAX_PLAYERS`]
+	pub const fn select_next(&mut self) {
+		self.players = self.players % MAX_PLAYERS + 1;
+	}
+}
+
+/// The race of all players to the exit of the current maze
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct Race {
+	exit: Option<TilePos>,
+	/// The index of the first player who reached the exit
+	pub winner: Option<usize>,
+}
+
+/// Get the devices controlling the players, by player index: the connected
+/// gamepads (ordered by their ids), then the left and right half of the
+/// keyboard
+fn devices(gamepads: &Gamepads) -> Vec<Device> {
+	let mut pads = gamepads.iter().collect::<Vec<_>>();
+	pads.sort_by_key(|gamepad| gamepad.id);
+
+	pads.into_iter()
+		.map(Device::Gamepad)
+		.chain([
+			Device::Keyboard(KeyboardHalf::Left),
+			Device::Keyboard(KeyboardHalf::Right),
+		])
+		.collect()
+}
+
+pub fn initialize(mut commands: Commands) {
+	commands.insert_resource(Multiplayer { players: 1 });
+	commands.insert_resource(Race::default());
+}
+
+/// Spawn or despawn players when the number of players changes
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn spawn(
+	mut commands: Commands,
+	multiplayer: Res<Multiplayer>,
+	settings: Res<MovementSettings>,
+	players: Query<(Entity, &Player, &PlayerSprites)>,
+) {
+	if !multiplayer.is_changed() {
+		return;
+	}
+
+	let Some(sprites) = players
+		.iter()
+		.find(|(_, p, _)| p.is_main())
+		.map(|(_, _, s)| s)
+	else {
+		return;
+	};
+
+	for (entity, player, _) in &players {
+		if player.0 >= multiplayer.players {
+			commands.entity(entity).despawn_recursive();
+		}
+	}
+
+	for index in 0..multiplayer.players {
+		if !players.iter().any(|(_, p, _)| p.0 == index) {
+			let start = tile_position(START.index());
+			player::spawn(
+				&mut commands,
+				Player(index),
+				sprites.clone(),
+				start,
+				*settings,
+			);
+		}
+	}
+}
+
+/// Read every player's [`PlayerInput`] from their [`Device`] in local
+/// multiplayer
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn input(
+	multiplayer: Res<Multiplayer>,
+	actions: Actions,
+	gamepads: Res<Gamepads>,
+	mut players: Query<(&Player, &mut PlayerInput)>,
+) {
+	if !multiplayer.is_enabled() {
+		return;
+	}
+
+	let devices = devices(&gamepads);
+
+	for (player, mut input) in &mut players {
+		let Some(&device) = devices.get(player.0) else {
+			*input = PlayerInput::default();
+			continue;
+		};
+
+		let value = |action| actions.device_value(device, action);
+		let up = value(Action::Up) - value(Action::Down);
+		let right = value(Action::Right) - value(Action::Left);
+
+		*input = PlayerInput {
+			up: up.clamp(-1.0, 1.0),
+			right: right.clamp(-1.0, 1.0),
+			sprint: actions.device_pressed(device, Action::Sprint),
+			dash: input.dash || actions.device_just_pressed(device, Action::Dash),
+		};
+	}
+}
+
+/// Start a new race when the maze is regenerated
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn start(mut regenerated: EventReader<MazeRegenerated>, mut race: ResMut<Race>) {
+	if let Some(event) = regenerated.read().last() {
+		*race = Race {
+			exit: Some(event.exit),
+			winner: None,
+		};
+	}
+}
+
+/// End the race once any player reaches the exit in local multiplayer
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn finish(
+	multiplayer: Res<Multiplayer>,
+	mut race: ResMut<Race>,
+	players: Query<(&Player, &Transform)>,
+) {
+	if !multiplayer.is_enabled() || race.winner.is_some() {
+		return;
+	}
+
+	let Some(exit) = race.exit else {
+		return;
+	};
+
+	let winner = players
+		.iter()
+		.find(|(_, transform)| nearest_tile(transform.translation.truncate()) == exit);
+
+	if let Some((player, _)) = winner {
+		info!("player {} reached the exit first", player.0 + 1);
+		race.winner = Some(player.0);
+	}
+}
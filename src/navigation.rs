@@ -0,0 +1,177 @@
+//! Click-to-move and tap-to-move: walking the player along the shortest route
+//! to a clicked or tapped tile.
+
+use bevy::{input::touch::Touch, prelude::*, window::PrimaryWindow};
+
+use crate::{
+	algorithms::find_route,
+	maze::{nearest_tile, tile_position, Maze, MazeRegenerated, TilePos},
+	player::Player,
+	touch::TouchControls,
+	util::PlayerInput,
+};
+
+/// The distance from a tile's center at which the player is considered to
+/// have reached the tile
+const ARRIVAL_DISTANCE: f32 = 12.0;
+/// The distance from the destination at which the player starts slowing down
+const SLOWDOWN_DISTANCE: f32 = 40.0;
+/// The size of the markers showing the route
+const MARKER_SIZE: f32 = 12.0;
+const MARKER_COLOR: Color = Color::rgba(1.0, 0.85, 0.4, 0.7);
+
+/// The route the player is automatically walking along
+#[derive(Debug, Clone, Default, Resource)]
+pub struct Route(pub Vec<TilePos>);
+
+/// A marker showing a tile of the [`Route`]
+#[derive(Debug, Clone, Copy, Component)]
+pub struct RouteMarker(TilePos);
+
+pub fn initialize(mut commands: Commands) {
+	commands.insert_resource(Route::default());
+}
+
+/// Find a route to the clicked or tapped tile
+///
+/// Clicks and taps on the UI (e.g. the menu) and touches grabbing the touch
+/// joystick are ignored
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn select(
+	mouse: Res<ButtonInput<MouseButton>>,
+	touches: Res<Touches>,
+	touch_controls: Res<TouchControls>,
+	window: Query<&Window, With<PrimaryWindow>>,
+	camera: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+	players: Query<(&Player, &Transform)>,
+	interactions: Query<&Interaction>,
+	maze: Res<Maze>,
+	mut route: ResMut<Route>,
+) {
+	let position = if mouse.just_pressed(MouseButton::Left) {
+		window.get_single().ok().and_then(Window::cursor_position)
+	} else {
+		touches
+			.iter_just_pressed()
+			.find(|t| !touch_controls.holds_joystick(t.id()))
+			.map(Touch::position)
+	};
+
+	let Some(position) = position else {
+		return;
+	};
+
+	if interactions.iter().any(|i| *i != Interaction::None) {
+		return;
+	}
+
+	let player = players.iter().find(|(p, _)| p.is_main());
+
+	let (Ok((camera, camera_transform)), Some((_, player))) = (camera.get_single(), player) else {
+		return;
+	};
+
+	let Some(target) = camera.viewport_to_world_2d(camera_transform, position) else {
+		return;
+	};
+
+	let from = nearest_tile(player.translation.truncate());
+	let to = nearest_tile(target);
+
+	if let Some(new_route) = find_route(&maze, from, to, maze.params) {
+		route.0 = new_route;
+	}
+}
+
+/// Walk the player along the [`Route`] by overriding the [`PlayerInput`],
+/// cancelling the route when there is any manual movement input
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn follow(
+	mut input: ResMut<PlayerInput>,
+	mut route: ResMut<Route>,
+	mut regenerated: EventReader<MazeRegenerated>,
+	players: Query<(&Player, &Transform)>,
+) {
+	if !regenerated.is_empty() {
+		regenerated.clear();
+		route.0.clear();
+	}
+
+	if route.0.is_empty() {
+		return;
+	}
+
+	if input.is_moving() {
+		route.0.clear();
+		return;
+	}
+
+	let Some((_, player)) = players.iter().find(|(p, _)| p.is_main()) else {
+		return;
+	};
+
+	let position = player.translation.truncate();
+
+	while let Some(&next) = route.0.first() {
+		if route.0.len() > 1 && position.distance(tile_position(next.index())) < ARRIVAL_DISTANCE {
+			route.0.remove(0);
+		} else {
+			break;
+		}
+	}
+
+	let Some(&next) = route.0.first() else {
+		return;
+	};
+
+	let offset = tile_position(next.index()) - position;
+	let distance = offset.length();
+
+	if route.0.len() == 1 && distance < ARRIVAL_DISTANCE {
+		route.0.clear();
+		return;
+	}
+
+	let speed = if route.0.len() == 1 {
+		(distance / SLOWDOWN_DISTANCE).min(1.0)
+	} else {
+		1.0
+	};
+
+	let direction = offset / distance * speed;
+	input.up = direction.y;
+	input.right = direction.x;
+}
+
+/// Show markers on the tiles of the [`Route`]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn preview(mut commands: Commands, route: Res<Route>, markers: Query<(Entity, &RouteMarker)>) {
+	if !route.is_changed() {
+		return;
+	}
+
+	for (entity, marker) in &markers {
+		if !route.0.contains(&marker.0) {
+			commands.entity(entity).despawn_recursive();
+		}
+	}
+
+	for &pos in &route.0 {
+		if markers.iter().any(|(_, m)| m.0 == pos) {
+			continue;
+		}
+
+		let Vec2 { x, y } = tile_position(pos.index());
+
+		commands.spawn((RouteMarker(pos), SpriteBundle {
+			sprite: Sprite {
+				color: MARKER_COLOR,
+				custom_size: Some(Vec2::splat(MARKER_SIZE)),
+				..default()
+			},
+			transform: Transform::from_xyz(x, y, 9.0),
+			..default()
+		}));
+	}
+}
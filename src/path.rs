@@ -41,7 +41,9 @@ pub fn spawn_initial(commands: &mut Commands, rng: &Rand, paths: &Paths) {
 	let mut limit = 2 * INITIAL_LIGHTS_LIMIT;
 
 	while let Some(pos) = current {
-		let idx = paths.0.get(pos).unwrap().index();
+		let Some(idx) = paths.0.get(pos).map(|pos| pos.index()) else {
+			break;
+		};
 		let Vec2 { mut x, mut y } = tile_position(idx);
 		current = paths.0.parent(pos);
 
@@ -49,7 +51,7 @@ pub fn spawn_initial(commands: &mut Commands, rng: &Rand, paths: &Paths) {
 
 		if limit == 0 {
 			break;
-		} else if limit % 2 == 0 {
+		} else if limit.is_multiple_of(2) {
 			continue;
 		}
 
@@ -93,7 +95,13 @@ pub fn spawn_more(
 	timer.0.tick(time.delta());
 
 	if timer.0.just_finished() {
-		let Vec3 { x, y, .. } = player.single().translation();
+		// Spawn near a random player in local multiplayer
+		let players = player.iter().collect::<Vec<_>>();
+		let Some(player) = rng.sample(&players) else {
+			return;
+		};
+
+		let Vec3 { x, y, .. } = player.translation();
 
 		commands.spawn((
 			Path,
@@ -124,6 +132,17 @@ pub fn spawn_more(
 #[derive(Component, Deref, DerefMut)]
 pub struct PathFlickerTimer(Timer);
 
+/// Get the squared distance from `pos` to the nearest player
+fn nearest_player_distance_squared<'a>(
+	players: impl IntoIterator<Item = &'a GlobalTransform>,
+	pos: Vec3,
+) -> f32 {
+	players
+		.into_iter()
+		.map(|player| player.translation().distance_squared(pos))
+		.fold(f32::INFINITY, f32::min)
+}
+
 #[derive(Component, Deref, DerefMut)]
 pub struct FadingOut(Timer);
 
@@ -137,8 +156,6 @@ pub fn flicker(
 		Without<FadingOut>,
 	>,
 ) {
-	let player = player.single().translation();
-
 	for (mut light, mut timer, trans) in &mut query {
 		timer.tick(time.delta());
 
@@ -146,7 +163,9 @@ pub fn flicker(
 			light.intensity = LIGHT_INITIAL_INTENSITY * ((*rng).f32() + 1.0) / 2.0;
 			light.intensity *= f32::min(
 				1.0,
-				5000.0 / (trans.translation().distance_squared(player) + f32::EPSILON),
+				5000.0
+					/ (nearest_player_distance_squared(&player, trans.translation())
+						+ f32::EPSILON),
 			);
 			timer.set_duration(Duration::from_secs_f64((*rng).f64() / 5.0));
 		}
@@ -223,7 +242,6 @@ pub fn fadeout(
 ) {
 	let distance = MOVEMENT_SPEED * time.delta_seconds();
 	let rotation = ROTATION_SPEED * time.delta_seconds();
-	let player = player.single().translation();
 
 	for (entity, mut trans, mut light, mut timer, mut fade, mut dir) in &mut query {
 		timer.tick(time.delta());
@@ -233,7 +251,8 @@ pub fn fadeout(
 			light.intensity = LIGHT_INITIAL_INTENSITY * ((*rng).f32() + 1.0) / 2.0;
 			light.intensity *= f32::min(
 				1.0,
-				5000.0 / (trans.translation.distance_squared(player) + f32::EPSILON),
+				5000.0
+					/ (nearest_player_distance_squared(&player, trans.translation) + f32::EPSILON),
 			);
 			light.intensity *= fade.0.fraction_remaining();
 		}
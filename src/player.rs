@@ -1,16 +1,17 @@
 use std::time::Duration;
 
-use bevy::prelude::*;
+use bevy::{math::bounding::Aabb2d, prelude::*};
 
 use super::{
-	maze::{
-		self,
-		Direction::{Bottom, Left, Right, Top},
-		Tile,
-	},
+	maze::{tile_position, Maze, MazeRegenerated, START},
 	PlayerInput,
 };
-use crate::util::{Rand, TurboRand};
+use crate::{
+	collision,
+	multiplayer::{Multiplayer, MAX_PLAYERS},
+	theme::{Theme, ThemeChanged},
+	util::{Rand, TurboRand},
+};
 
 const TILE_SIZE: Vec2 = Vec2::new(24.0, 32.0);
 const TILE_AMOUNT_IDLE: usize = 10;
@@ -18,27 +19,154 @@ const TILE_AMOUNT_WALKING: usize = 10;
 const TILE_SCALE: f32 = 2.0;
 const TILE_FRAME_TIME_SECONDS: f32 = 0.1;
 
-const MOVEMENT_SPEED: f32 = 150.0;
 const LIGHT_INITIAL_INTENSITY: f32 = 50_000_000_000.0;
+/// The tint of each player's sprite, so that local players can tell
+/// themselves apart
+const COLORS: [Color; MAX_PLAYERS] = [
+	Color::WHITE,
+	Color::rgb(0.6, 0.8, 1.0),
+	Color::rgb(1.0, 0.6, 0.6),
+	Color::rgb(0.6, 1.0, 0.6),
+];
+
+/// A player, with the index of the player in local multiplayer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct Player(pub usize);
+
+impl Player {
+	/// Whether this is the first player, who also uses the touch controls,
+	/// click-to-move and replays
+	pub const fn is_main(self) -> bool {
+		self.0 == 0
+	}
+}
 
-#[derive(Debug, Component)]
-pub struct Player {
+/// The sprite sheets used for animating the player (or the ghost of a
+/// previous run)
+#[derive(Debug, Clone, Component)]
+pub struct PlayerSprites {
 	idle_atlas: Handle<TextureAtlasLayout>,
 	idle_texture: Handle<Image>,
 	walking_atlas: Handle<TextureAtlasLayout>,
 	walking_texture: Handle<Image>,
 }
 
-#[derive(Debug, Component)]
+#[derive(Debug, Clone, Component)]
 pub struct Movement {
 	is_walking: bool,
 	is_right: bool,
+	/// The player's position before the last movement step, from which
+	/// collisions are checked
+	pub start: Vec2,
+	/// The player's current velocity
+	pub velocity: Vec2,
+}
+
+impl Movement {
+	/// Create a new `Movement` standing still at `start` and facing right
+	pub const fn new(start: Vec2) -> Self {
+		Self {
+			is_walking: false,
+			is_right: true,
+			start,
+			velocity: Vec2::ZERO,
+		}
+	}
+
+	/// Set the velocity, facing in its direction (if it has a horizontal
+	/// component) and walking if it is large enough
+	pub fn set_velocity(&mut self, velocity: Vec2) {
+		if velocity.x > 0.0 {
+			self.is_right = true;
+		} else if velocity.x < 0.0 {
+			self.is_right = false;
+		}
+
+		self.is_walking = velocity.length() > 1.0;
+		self.velocity = velocity;
+	}
+}
+
+/// Tunable parameters of the player's movement
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct MovementSettings {
+	/// The maximum walking speed
+	pub walk_speed: f32,
+	/// The maximum speed while sprinting, as a multiple of `walk_speed`
+	pub sprint_multiplier: f32,
+	/// How quickly the player speeds up towards the maximum speed
+	pub acceleration: f32,
+	/// How quickly the player slows down when there is no input
+	pub deceleration: f32,
+	/// The speed of a dash
+	pub dash_speed: f32,
+	/// How long a dash lasts in seconds
+	pub dash_duration: f32,
+	/// The time between the starts of two dashes in seconds
+	pub dash_cooldown: f32,
+	/// The stamina used per second of sprinting
+	pub sprint_drain: f32,
+	/// The stamina used by a dash
+	pub dash_cost: f32,
+	/// The stamina regained per second while not sprinting or dashing
+	pub stamina_regen: f32,
+}
+
+impl Default for MovementSettings {
+	fn default() -> Self {
+		Self {
+			walk_speed: 150.0,
+			sprint_multiplier: 1.8,
+			acceleration: 1200.0,
+			deceleration: 1500.0,
+			dash_speed: 700.0,
+			dash_duration: 0.15,
+			dash_cooldown: 1.0,
+			sprint_drain: 0.4,
+			dash_cost: 0.3,
+			stamina_regen: 0.25,
+		}
+	}
+}
+
+/// The player's stamina (from `0.0` to `1.0`) used for sprinting and dashing,
+/// and the state of the player's dash
+#[derive(Debug, Clone, Component)]
+pub struct Stamina {
+	/// The remaining stamina
+	pub current: f32,
+	dash: Timer,
+	cooldown: Timer,
+	dash_direction: Vec2,
+}
+
+impl Stamina {
+	/// Create a new full `Stamina` with the dash ready to be used
+	pub fn new(settings: MovementSettings) -> Self {
+		let mut dash = Timer::from_seconds(settings.dash_duration, TimerMode::Once);
+		let mut cooldown = Timer::from_seconds(settings.dash_cooldown, TimerMode::Once);
+		dash.tick(dash.duration());
+		cooldown.tick(cooldown.duration());
+
+		Self {
+			current: 1.0,
+			dash,
+			cooldown,
+			dash_direction: Vec2::ZERO,
+		}
+	}
+
+	/// Whether the player is currently dashing
+	pub fn is_dashing(&self) -> bool {
+		!self.dash.finished()
+	}
 }
 
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
 pub fn initialize(
 	mut commands: Commands,
 	asset_server: Res<AssetServer>,
+	settings: Res<MovementSettings>,
 	mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
 ) {
 	let idle_handle = asset_server.load("maze/player-idle.png");
@@ -50,39 +178,49 @@ pub fn initialize(
 		TextureAtlasLayout::from_grid(TILE_SIZE, 1, TILE_AMOUNT_WALKING, None, None);
 	let walking_atlas_handle = texture_atlases.add(walking_atlas);
 
+	let sprites = PlayerSprites {
+		idle_atlas: idle_atlas_handle,
+		idle_texture: idle_handle,
+		walking_atlas: walking_atlas_handle,
+		walking_texture: walking_handle,
+	};
+
+	spawn(&mut commands, Player(0), sprites, Vec2::ZERO, *settings);
+}
+
+/// Spawn a player with a torch at `position`
+pub fn spawn(
+	commands: &mut Commands,
+	player: Player,
+	sprites: PlayerSprites,
+	position: Vec2,
+	settings: MovementSettings,
+) {
 	commands
 		.spawn((
-			Player {
-				idle_atlas: idle_atlas_handle.clone(),
-				idle_texture: idle_handle.clone(),
-				walking_atlas: walking_atlas_handle,
-				walking_texture: walking_handle,
-			},
-			Movement {
-				is_right: true,
-				is_walking: false,
-			},
+			player,
+			Movement::new(position),
+			Stamina::new(settings),
+			PlayerInput::default(),
 			SpriteSheetBundle {
 				atlas: TextureAtlas {
-					layout: idle_atlas_handle,
+					layout: sprites.idle_atlas.clone(),
+					..default()
+				},
+				texture: sprites.idle_texture.clone(),
+				sprite: Sprite {
+					color: COLORS.get(player.0).copied().unwrap_or(Color::WHITE),
 					..default()
 				},
-				texture: idle_handle,
-				sprite: Sprite::default(),
 				transform: Transform {
-					translation: Vec3 {
-						z: 10.0,
-						..default()
-					},
+					translation: position.extend(10.0),
 					scale: Vec3::splat(TILE_SCALE),
 					..default()
 				},
 				..default()
 			},
-			AnimationTimer(Timer::from_seconds(
-				TILE_FRAME_TIME_SECONDS,
-				TimerMode::Repeating,
-			)),
+			sprites,
+			AnimationTimer::new(),
 		))
 		.with_children(|builder| {
 			builder.spawn((
@@ -109,25 +247,130 @@ pub fn initialize(
 		});
 }
 
+/// Give the main player the [`PlayerInput`] resource (from the input map,
+/// touch controls, click-to-move or a replay), unless playing local
+/// multiplayer, where every player's input comes from their own device
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn main_input(
+	mut input: ResMut<PlayerInput>,
+	multiplayer: Res<Multiplayer>,
+	mut players: Query<(&Player, &mut PlayerInput)>,
+) {
+	if !multiplayer.is_enabled() {
+		for (_, mut player_input) in players.iter_mut().filter(|(p, _)| p.is_main()) {
+			*player_input = *input;
+		}
+	}
+
+	input.dash = false;
+}
+
+/// Move the players according to their [`PlayerInput`]
+///
+/// This runs in `FixedUpdate`, so that the movement only depends on the inputs
+/// and not on the frame rate, which makes replays deterministic
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
 pub fn movement(
 	time: Res<Time>,
-	input: Res<PlayerInput>,
-	mut query: Query<(&mut Transform, &mut Movement), With<Player>>,
+	settings: Res<MovementSettings>,
+	mut query: Query<
+		(
+			&mut Transform,
+			&mut Movement,
+			&mut Stamina,
+			&mut PlayerInput,
+		),
+		With<Player>,
+	>,
 ) {
-	let distance = MOVEMENT_SPEED * time.delta_seconds();
+	let delta = time.delta_seconds();
 
-	for (mut trans, mut movement) in &mut query {
+	for (mut trans, mut movement, mut stamina, mut input) in &mut query {
 		if input.right > 0.0 {
 			movement.is_right = true;
 		} else if input.right < 0.0 {
 			movement.is_right = false;
 		}
 
-		movement.is_walking = input.is_moving();
+		stamina.dash.tick(time.delta());
+		stamina.cooldown.tick(time.delta());
+
+		let direction = Vec2::from(*input).clamp_length_max(1.0);
+
+		if input.dash
+			&& input.is_moving()
+			&& stamina.cooldown.finished()
+			&& stamina.current >= settings.dash_cost
+		{
+			stamina.current -= settings.dash_cost;
+			stamina.dash_direction = direction.normalize_or_zero();
+			stamina.dash.reset();
+			stamina.cooldown.reset();
+		}
+
+		let is_sprinting = input.sprint && input.is_moving() && stamina.current > 0.0;
+
+		if stamina.is_dashing() {
+			movement.velocity = stamina.dash_direction * settings.dash_speed;
+		} else {
+			let max_speed = if is_sprinting {
+				settings.walk_speed * settings.sprint_multiplier
+			} else {
+				settings.walk_speed
+			};
+
+			let rate = if input.is_moving() {
+				settings.acceleration
+			} else {
+				settings.deceleration
+			};
+
+			let target = direction * max_speed;
+			let difference = target - movement.velocity;
+			let step = rate * delta;
+
+			movement.velocity = if difference.length() <= step {
+				target
+			} else {
+				movement.velocity + difference.normalize() * step
+			};
+		}
+
+		if is_sprinting {
+			stamina.current = settings.sprint_drain.mul_add(-delta, stamina.current);
+		} else if !stamina.is_dashing() {
+			stamina.current = settings.stamina_regen.mul_add(delta, stamina.current);
+		}
+
+		stamina.current = stamina.current.clamp(0.0, 1.0);
+
+		movement.is_walking = movement.velocity.length() > 1.0;
+		movement.start = trans.translation.truncate();
 
-		trans.translation.y += distance * input.up;
-		trans.translation.x += distance * input.right;
+		trans.translation += (movement.velocity * delta).extend(0.0);
+		input.dash = false;
+	}
+}
+
+/// Update the player's sprites and light color when the theme changes
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn apply_theme(
+	mut events: EventReader<ThemeChanged>,
+	themes: Res<Assets<Theme>>,
+	mut sprites: Query<&mut PlayerSprites>,
+	mut light: Query<&mut PointLight, With<FlickerTimer>>,
+) {
+	let Some(theme) = events.read().last().and_then(|e| themes.get(e.0)) else {
+		return;
+	};
+
+	for mut sprites in &mut sprites {
+		sprites.idle_texture = theme.player_idle.clone();
+		sprites.walking_texture = theme.player_walking.clone();
+	}
+
+	for mut light in &mut light {
+		light.color = theme.player_light;
 	}
 }
 
@@ -150,135 +393,70 @@ pub fn light_flicker(
 	}
 }
 
+/// Move the player back to the start when the maze is regenerated
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
-#[allow(clippy::too_many_lines)]
-pub fn collision(
-	mut player: Query<&mut Transform, With<Player>>,
-	tiles: Query<(&Transform, &Tile), Without<Player>>,
+pub fn respawn(
+	mut events: EventReader<MazeRegenerated>,
+	settings: Res<MovementSettings>,
+	mut player: Query<
+		(
+			&mut Transform,
+			&mut Movement,
+			&mut Stamina,
+			&mut TextureAtlas,
+		),
+		With<Player>,
+	>,
 ) {
-	let mut player = player.single_mut();
-
-	let half_size = maze::TILE_SIZE / 2.0;
-	let inner_half = half_size - maze::WALL_THICKNESS;
-	let scaled_inner = inner_half * maze::TILE_SCALE;
-
-	let player_edges = [
-		player.translation.y + TILE_SIZE.y * TILE_SCALE / 2.0,
-		player.translation.x + TILE_SIZE.x * TILE_SCALE / 2.0,
-		player.translation.y - TILE_SIZE.y * TILE_SCALE / 2.0,
-		player.translation.x - TILE_SIZE.x * TILE_SCALE / 2.0,
-	];
-
-	let mut nearby_tiles = tiles
-		.iter()
-		.filter(|(t, ..)| {
-			let diff = (t.translation - player.translation).abs();
-			diff.x < 1.5 * maze::TILE_SIZE.x * maze::TILE_SCALE
-				&& diff.y < 1.5 * maze::TILE_SIZE.y * maze::TILE_SCALE
-		})
-		.map(|(trans, tile)| {
-			if tile.is_grass() {
-				(trans, Tile::OPEN)
-			} else {
-				(trans, *tile)
-			}
-		})
-		.collect::<Vec<_>>();
-
-	if nearby_tiles.len() < 9 {
+	if events.is_empty() {
 		return;
 	}
 
-	#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
-	nearby_tiles.sort_by_key(|(t, _)| (-t.translation.y as i32, t.translation.x as i32));
-	let nearby_tiles = nearby_tiles;
+	events.clear();
 
-	let current_tile = nearby_tiles[4];
-
-	if current_tile.1.is_grass() {
-		return;
-	}
+	let start = tile_position(START.index());
 
-	let mut tile_edges = [
-		current_tile.0.translation.y + scaled_inner.y,
-		current_tile.0.translation.x + scaled_inner.x,
-		current_tile.0.translation.y - scaled_inner.y,
-		current_tile.0.translation.x - scaled_inner.x,
-	];
-
-	let mut is_above = player_edges[0] > tile_edges[0];
-	let mut is_right = player_edges[1] > tile_edges[1];
-	let mut is_below = player_edges[2] < tile_edges[2];
-	let mut is_left = player_edges[3] < tile_edges[3];
-
-	if current_tile.1.is_closed(Top) && is_above {
-		player.translation.y -= player_edges[0] - tile_edges[0];
-		tile_edges[0] = current_tile.0.translation.y + scaled_inner.y;
-		is_above = false;
-	}
-
-	if current_tile.1.is_closed(Right) && is_right {
-		player.translation.x -= player_edges[1] - tile_edges[1];
-		tile_edges[1] = current_tile.0.translation.x + scaled_inner.x;
-		is_right = false;
+	for (mut trans, mut movement, mut stamina, mut atlas) in &mut player {
+		trans.translation.x = start.x;
+		trans.translation.y = start.y;
+		movement.start = start;
+		movement.velocity = Vec2::ZERO;
+		*stamina = Stamina::new(*settings);
+		movement.is_walking = false;
+		movement.is_right = true;
+		atlas.index = 0;
 	}
+}
 
-	if current_tile.1.is_closed(Bottom) && is_below {
-		player.translation.y -= player_edges[2] - tile_edges[2];
-		tile_edges[2] = current_tile.0.translation.y - scaled_inner.y;
-		is_below = false;
-	}
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn collision(
+	mut player: Query<(&mut Transform, &mut Movement), With<Player>>,
+	maze: Res<Maze>,
+) {
+	let half_size = TILE_SIZE * TILE_SCALE / 2.0;
 
-	if current_tile.1.is_closed(Left) && is_left {
-		player.translation.x -= player_edges[3] - tile_edges[3];
-		tile_edges[3] = current_tile.0.translation.x - scaled_inner.x;
-		is_left = false;
-	}
+	for (mut player, mut movement) in &mut player {
+		let from = movement.start;
+		let to = player.translation.truncate();
 
-	let player_tile_diff = (player.translation - current_tile.0.translation).abs();
-	let coll_is_horizontal = player_tile_diff.y > player_tile_diff.x;
+		let area = Aabb2d {
+			min: from.min(to) - half_size,
+			max: from.max(to) + half_size,
+		};
 
-	if (nearby_tiles[3].1.is_closed(Top) || nearby_tiles[1].1.is_closed(Left))
-		&& is_above
-		&& is_left
-	{
-		if coll_is_horizontal {
-			player.translation.x -= player_edges[3] - tile_edges[3];
-		} else {
-			player.translation.y -= player_edges[0] - tile_edges[0];
-		}
-	}
+		let walls = collision::walls_near(&maze.tiles, area);
+		let pos = collision::sweep(&walls, half_size, from, to);
 
-	if (nearby_tiles[3].1.is_closed(Bottom) || nearby_tiles[7].1.is_closed(Left))
-		&& is_below
-		&& is_left
-	{
-		if coll_is_horizontal {
-			player.translation.x -= player_edges[3] - tile_edges[3];
-		} else {
-			player.translation.y -= player_edges[2] - tile_edges[2];
-		}
-	}
+		player.translation.x = pos.x;
+		player.translation.y = pos.y;
 
-	if (nearby_tiles[5].1.is_closed(Top) || nearby_tiles[1].1.is_closed(Right))
-		&& is_above
-		&& is_right
-	{
-		if coll_is_horizontal {
-			player.translation.x -= player_edges[1] - tile_edges[1];
-		} else {
-			player.translation.y -= player_edges[0] - tile_edges[0];
+		// Stop moving into the walls which blocked the movement
+		if (pos.x - to.x).abs() > 0.001 {
+			movement.velocity.x = 0.0;
 		}
-	}
 
-	if (nearby_tiles[5].1.is_closed(Bottom) || nearby_tiles[7].1.is_closed(Right))
-		&& is_below
-		&& is_right
-	{
-		if coll_is_horizontal {
-			player.translation.x -= player_edges[1] - tile_edges[1];
-		} else {
-			player.translation.y -= player_edges[2] - tile_edges[2];
+		if (pos.y - to.y).abs() > 0.001 {
+			movement.velocity.y = 0.0;
 		}
 	}
 }
@@ -286,32 +464,49 @@ pub fn collision(
 #[derive(Component, Deref, DerefMut)]
 pub struct AnimationTimer(Timer);
 
+impl AnimationTimer {
+	pub fn new() -> Self {
+		Self(Timer::from_seconds(
+			TILE_FRAME_TIME_SECONDS,
+			TimerMode::Repeating,
+		))
+	}
+}
+
 #[allow(clippy::type_complexity)]
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
 pub fn animation(
 	time: Res<Time>,
+	settings: Res<MovementSettings>,
 	mut query: Query<(
 		&Movement,
-		&Player,
+		&PlayerSprites,
 		&mut AnimationTimer,
 		&mut Sprite,
 		&mut TextureAtlas,
 		&mut Handle<Image>,
 	)>,
 ) {
-	for (movement, player, mut timer, mut sprite, mut atlas, mut texture) in &mut query {
-		timer.tick(time.delta());
+	for (movement, sprites, mut timer, mut sprite, mut atlas, mut texture) in &mut query {
+		// Walk faster when moving faster
+		let speed = if movement.is_walking {
+			(movement.velocity.length() / settings.walk_speed).clamp(0.5, 3.0)
+		} else {
+			1.0
+		};
+
+		timer.tick(time.delta().mul_f32(speed));
 		if timer.just_finished() {
 			atlas.index += 1;
 		}
 
 		if movement.is_walking {
-			atlas.layout = player.walking_atlas.clone();
-			*texture = player.walking_texture.clone();
+			atlas.layout = sprites.walking_atlas.clone();
+			*texture = sprites.walking_texture.clone();
 			atlas.index %= TILE_AMOUNT_WALKING;
 		} else {
-			atlas.layout = player.idle_atlas.clone();
-			*texture = player.idle_texture.clone();
+			atlas.layout = sprites.idle_atlas.clone();
+			*texture = sprites.idle_texture.clone();
 			atlas.index %= TILE_AMOUNT_IDLE;
 		}
 
@@ -0,0 +1,140 @@
+//! Built-in presets of maze parameters, and profiles of maze parameters saved
+//! by the player under a name.
+
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	algorithms::{DirectionalBias, MazeParams},
+	storage,
+};
+
+/// The name of the file the profiles are saved in
+const FILE_NAME: &str = "profiles.ron";
+
+/// A built-in preset of maze parameters
+#[derive(Debug, Clone, Copy)]
+pub struct Preset {
+	/// The id of the preset's name message
+	pub message: &'static str,
+	pub params: MazeParams,
+}
+
+/// The built-in presets, in the order they are listed in
+pub const PRESETS: [Preset; 5] = [
+	Preset {
+		message: "preset-tiny",
+		params: MazeParams {
+			width: 5,
+			height: 5,
+			rooms: 0,
+			bias: DirectionalBias::None,
+		},
+	},
+	Preset {
+		message: "preset-classic",
+		params: MazeParams {
+			width: 15,
+			height: 11,
+			rooms: 3,
+			bias: DirectionalBias::None,
+		},
+	},
+	Preset {
+		message: "preset-corridors",
+		params: MazeParams {
+			width: 40,
+			height: 12,
+			rooms: 2,
+			bias: DirectionalBias::VeryHorizontal,
+		},
+	},
+	Preset {
+		message: "preset-towers",
+		params: MazeParams {
+			width: 12,
+			height: 40,
+			rooms: 2,
+			bias: DirectionalBias::VeryVertical,
+		},
+	},
+	Preset {
+		message: "preset-huge",
+		params: MazeParams {
+			width: 100,
+			height: 100,
+			rooms: 50,
+			bias: DirectionalBias::None,
+		},
+	},
+];
+
+/// Maze parameters saved by the player under a name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+	pub name: String,
+	pub params: MazeParams,
+}
+
+/// The player's saved profiles, in the order they were saved in
+#[derive(Debug, Clone, Default, Resource, Serialize, Deserialize)]
+pub struct Profiles(pub Vec<Profile>);
+
+impl Profiles {
+	/// Save `params` as the profile called `name`, replacing the parameters of
+	/// an existing profile with the same name
+	pub fn insert(&mut self, name: String, params: MazeParams) {
+		match self.0.iter_mut().find(|profile| profile.name == name) {
+			Some(profile) => profile.params = params,
+			None => self.0.push(Profile { name, params }),
+		}
+	}
+
+	/// Remove the profile at `index`, if there is one
+	pub fn remove(&mut self, index: usize) {
+		if index < self.0.len() {
+			self.0.remove(index);
+		}
+	}
+
+	fn load() -> Self {
+		let Some(saved) = storage::load_config(FILE_NAME) else {
+			return Self::default();
+		};
+
+		let mut profiles = ron::from_str::<Self>(&saved).unwrap_or_else(|e| {
+			warn!("invalid saved profiles: {e}");
+			Self::default()
+		});
+
+		profiles.0.retain(|profile| {
+			let valid = profile.params.is_valid();
+
+			if !valid {
+				warn!("invalid maze parameters in profile {:?}", profile.name);
+			}
+
+			valid
+		});
+
+		profiles
+	}
+}
+
+pub fn initialize(mut commands: Commands) {
+	commands.insert_resource(Profiles::load());
+}
+
+/// Save the [`Profiles`] whenever they change
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn save(profiles: Res<Profiles>) {
+	if !profiles.is_changed() || profiles.is_added() {
+		return;
+	}
+
+	match ron::ser::to_string_pretty(&*profiles, PrettyConfig::default()) {
+		Ok(saved) => storage::save_config(FILE_NAME, &saved),
+		Err(e) => warn!("could not serialize profiles: {e}"),
+	}
+}
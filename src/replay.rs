@@ -0,0 +1,257 @@
+//! Recording the player's inputs along with the maze they were used in, and
+//! playing them back deterministically (e.g. to share runs or reproduce bugs).
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	algorithms::MazeParams,
+	bindings::{Action, Actions},
+	maze::{MazeError, MazeRegenerated, RegenerateMaze},
+	multiplayer::Multiplayer,
+	storage,
+	util::PlayerInput,
+};
+
+/// The name of the file the last recorded replay is saved in
+const FILE_NAME: &str = "replay.ron";
+/// The shortest timestep a replay can have, since shorter ones would make the
+/// game run an unreasonable number of movement steps every frame
+const MIN_TIMESTEP: Duration = Duration::from_millis(1);
+
+/// A recorded run through a maze
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+	/// The seed the maze was generated with
+	pub seed: u64,
+	/// The parameters the maze was generated with
+	pub params: MazeParams,
+	/// The length of a movement step
+	pub timestep: Duration,
+	/// The input of every movement step, with runs of identical inputs stored
+	/// once along with their length
+	pub inputs: Vec<(u32, PlayerInput)>,
+}
+
+impl Replay {
+	/// Add the input of the next movement step
+	fn push(&mut self, input: PlayerInput) {
+		match self.inputs.last_mut() {
+			Some((count, last)) if *last == input => *count += 1,
+			_ => self.inputs.push((1, input)),
+		}
+	}
+
+	/// Load the saved replay, if there is one
+	///
+	/// # Errors
+	/// Returns [`MazeError::Replay`] if the saved replay is invalid, including
+	/// if its maze can not be generated
+	fn load() -> Result<Option<Self>, MazeError> {
+		let Some(saved) = storage::load_config(FILE_NAME) else {
+			return Ok(None);
+		};
+
+		match ron::from_str::<Self>(&saved) {
+			Ok(replay) if replay.timestep < MIN_TIMESTEP => Err(MazeError::Replay(format!(
+				"the timestep {:?} is shorter than {MIN_TIMESTEP:?}",
+				replay.timestep
+			))),
+			Ok(replay) if !replay.params.is_valid() => Err(MazeError::Replay(format!(
+				"invalid maze parameters {:?}",
+				replay.params
+			))),
+			Ok(replay) => Ok(Some(replay)),
+			Err(e) => Err(MazeError::Replay(e.to_string())),
+		}
+	}
+
+	fn save(&self) {
+		match ron::to_string(self) {
+			Ok(saved) => storage::save_config(FILE_NAME, &saved),
+			Err(e) => warn!("could not serialize replay: {e}"),
+		}
+	}
+}
+
+/// Whether a replay is being recorded or played back
+#[derive(Debug, Clone, Default, Resource)]
+pub enum ReplayState {
+	#[default]
+	Idle,
+	/// Waiting for the maze to be regenerated to start recording
+	StartingRecording,
+	Recording(Replay),
+	/// Waiting for the maze to be regenerated with the replay's seed to start
+	/// playing it back
+	StartingPlayback(Replay),
+	/// Playing back the replay, currently at the `step`th step of its `run`th
+	/// run of identical inputs
+	Playing {
+		replay: Replay,
+		run: usize,
+		step: u32,
+	},
+}
+
+impl ReplayState {
+	pub const fn is_recording(&self) -> bool {
+		matches!(self, Self::StartingRecording | Self::Recording(_))
+	}
+
+	pub const fn is_playing(&self) -> bool {
+		matches!(self, Self::StartingPlayback(_) | Self::Playing { .. })
+	}
+}
+
+/// Run condition for systems which should not run while a replay is being
+/// played back (like the ones reading the player's input)
+pub fn is_playing(state: Res<ReplayState>) -> bool {
+	state.is_playing()
+}
+
+pub fn initialize(mut commands: Commands) {
+	commands.insert_resource(ReplayState::default());
+}
+
+/// Start or stop recording ([`Action::Record`]) or playing back
+/// ([`Action::Replay`]) a replay
+///
+/// Both recording and playback start in a newly generated maze, with the
+/// same seed as the replay for playback. Recordings are saved once they stop.
+/// Replays only contain the main player's input, so they are not available in
+/// local multiplayer. Invalid saved replays are reported instead of played.
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn toggle(
+	actions: Actions,
+	multiplayer: Res<Multiplayer>,
+	mut state: ResMut<ReplayState>,
+	mut params: ResMut<MazeParams>,
+	mut events: EventWriter<RegenerateMaze>,
+	mut errors: EventWriter<MazeError>,
+) {
+	if multiplayer.is_enabled() {
+		return;
+	}
+
+	if actions.just_pressed(Action::Record) {
+		if let ReplayState::Recording(replay) = &*state {
+			replay.save();
+			*state = ReplayState::Idle;
+		} else {
+			*state = ReplayState::StartingRecording;
+			events.send(RegenerateMaze::default());
+		}
+	}
+
+	if actions.just_pressed(Action::Replay) {
+		if state.is_playing() {
+			*state = ReplayState::Idle;
+		} else {
+			match Replay::load() {
+				Ok(Some(replay)) => {
+					*params = replay.params;
+					events.send(RegenerateMaze {
+						seed: Some(replay.seed),
+					});
+					*state = ReplayState::StartingPlayback(replay);
+				}
+				Ok(None) => {}
+				Err(e) => e.report(&mut errors),
+			}
+		}
+	}
+}
+
+/// Use the replay's timestep while it is being played back, and restore the
+/// previous timestep once playback stops (in any way)
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn timestep(
+	state: Res<ReplayState>,
+	mut time: ResMut<Time<Fixed>>,
+	mut previous: Local<Option<Duration>>,
+) {
+	let replay_timestep = match &*state {
+		ReplayState::StartingPlayback(replay) | ReplayState::Playing { replay, .. } => {
+			Some(replay.timestep)
+		}
+		_ => None,
+	};
+
+	match (replay_timestep, *previous) {
+		(Some(step), None) => {
+			*previous = Some(time.timestep());
+			time.set_timestep(step);
+		}
+		(None, Some(step)) => {
+			time.set_timestep(step);
+			*previous = None;
+		}
+		_ => {}
+	}
+}
+
+/// Start recording or playing back once the maze is regenerated, or stop when
+/// the maze is regenerated during a recording or playback
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn start(
+	mut regenerated: EventReader<MazeRegenerated>,
+	mut state: ResMut<ReplayState>,
+	time: Res<Time<Fixed>>,
+) {
+	let Some(event) = regenerated.read().last() else {
+		return;
+	};
+
+	*state = match std::mem::take(&mut *state) {
+		ReplayState::StartingRecording => ReplayState::Recording(Replay {
+			seed: event.seed,
+			params: event.params,
+			timestep: time.timestep(),
+			inputs: Vec::new(),
+		}),
+		ReplayState::StartingPlayback(replay) => ReplayState::Playing {
+			replay,
+			run: 0,
+			step: 0,
+		},
+		ReplayState::Recording(replay) => {
+			replay.save();
+			ReplayState::Idle
+		}
+		ReplayState::Idle | ReplayState::Playing { .. } => ReplayState::Idle,
+	};
+}
+
+/// Record the [`PlayerInput`] of this movement step
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn record(mut state: ResMut<ReplayState>, input: Res<PlayerInput>) {
+	if let ReplayState::Recording(replay) = &mut *state {
+		replay.push(*input);
+	}
+}
+
+/// Replace the [`PlayerInput`] with the replay's input for this movement step
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn playback(mut state: ResMut<ReplayState>, mut input: ResMut<PlayerInput>) {
+	let ReplayState::Playing { replay, run, step } = &mut *state else {
+		return;
+	};
+
+	let Some(&(count, recorded)) = replay.inputs.get(*run) else {
+		info!("replay finished");
+		*input = PlayerInput::default();
+		*state = ReplayState::Idle;
+		return;
+	};
+
+	*input = recorded;
+	*step += 1;
+
+	if *step >= count {
+		*run += 1;
+		*step = 0;
+	}
+}
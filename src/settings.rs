@@ -0,0 +1,295 @@
+//! The player's preferences: graphics, audio, language and the parameters of
+//! the first maze.
+//!
+//! They are saved as TOML in the platform's config directory on native and in
+//! `localStorage` on wasm, and loaded before the app is built, so that e.g. the
+//! window is created in the right mode.
+
+use bevy::{
+	pbr::{DirectionalLightShadowMap, PointLightShadowMap},
+	prelude::*,
+	window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	algorithms::MazeParams,
+	locale::{Language, Locale},
+	player::FlickerTimer,
+	storage,
+};
+
+/// The name of the file the settings are saved in
+const FILE_NAME: &str = "settings.toml";
+
+/// How the window is shown on native
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+	Windowed,
+	Borderless,
+	Fullscreen,
+}
+
+impl DisplayMode {
+	pub const fn window_mode(self) -> WindowMode {
+		match self {
+			Self::Windowed => WindowMode::Windowed,
+			Self::Borderless => WindowMode::BorderlessFullscreen,
+			Self::Fullscreen => WindowMode::Fullscreen,
+		}
+	}
+
+	/// The id of the display mode's message
+	pub const fn message(self) -> &'static str {
+		match self {
+			Self::Windowed => "settings-display-windowed",
+			Self::Borderless => "settings-display-borderless",
+			Self::Fullscreen => "settings-display-fullscreen",
+		}
+	}
+
+	#[must_use]
+	pub const fn next(self) -> Self {
+		match self {
+			Self::Windowed => Self::Borderless,
+			Self::Borderless => Self::Fullscreen,
+			Self::Fullscreen => Self::Windowed,
+		}
+	}
+}
+
+/// The number of samples used for multisample anti-aliasing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Antialiasing {
+	Off,
+	Msaa2,
+	Msaa4,
+	Msaa8,
+}
+
+impl Antialiasing {
+	pub const fn msaa(self) -> Msaa {
+		match self {
+			Self::Off => Msaa::Off,
+			Self::Msaa2 => Msaa::Sample2,
+			Self::Msaa4 => Msaa::Sample4,
+			Self::Msaa8 => Msaa::Sample8,
+		}
+	}
+
+	/// The id of the anti-aliasing's message
+	pub const fn message(self) -> &'static str {
+		match self {
+			Self::Off => "settings-off",
+			Self::Msaa2 => "settings-msaa-2",
+			Self::Msaa4 => "settings-msaa-4",
+			Self::Msaa8 => "settings-msaa-8",
+		}
+	}
+
+	/// The next sample count, skipping the ones not supported by WebGL on wasm
+	#[must_use]
+	pub const fn next(self) -> Self {
+		match self {
+			Self::Off if cfg!(target_arch = "wasm32") => Self::Msaa4,
+			Self::Off => Self::Msaa2,
+			Self::Msaa2 => Self::Msaa4,
+			Self::Msaa4 if cfg!(target_arch = "wasm32") => Self::Off,
+			Self::Msaa4 => Self::Msaa8,
+			Self::Msaa8 => Self::Off,
+		}
+	}
+
+	/// The closest sample count supported on this platform, as WebGL on wasm
+	/// only supports 4 samples
+	#[must_use]
+	pub const fn supported(self) -> Self {
+		match self {
+			Self::Msaa2 | Self::Msaa8 if cfg!(target_arch = "wasm32") => Self::Msaa4,
+			_ => self,
+		}
+	}
+}
+
+/// The quality of the lights' shadows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LightQuality {
+	/// No shadows
+	Low,
+	/// Shadows with Bevy's default shadow map sizes
+	Medium,
+	/// Shadows with larger shadow maps
+	High,
+}
+
+impl LightQuality {
+	/// Whether the lights cast shadows
+	pub const fn shadows(self) -> bool {
+		!matches!(self, Self::Low)
+	}
+
+	pub const fn directional_shadow_map(self) -> DirectionalLightShadowMap {
+		let size = match self {
+			Self::Low | Self::Medium => 2048,
+			Self::High => 4096,
+		};
+
+		DirectionalLightShadowMap { size }
+	}
+
+	pub const fn point_shadow_map(self) -> PointLightShadowMap {
+		let size = match self {
+			Self::Low | Self::Medium => 1024,
+			Self::High => 2048,
+		};
+
+		PointLightShadowMap { size }
+	}
+
+	/// The id of the light quality's message
+	pub const fn message(self) -> &'static str {
+		match self {
+			Self::Low => "settings-quality-low",
+			Self::Medium => "settings-quality-medium",
+			Self::High => "settings-quality-high",
+		}
+	}
+
+	#[must_use]
+	pub const fn next(self) -> Self {
+		match self {
+			Self::Low => Self::Medium,
+			Self::Medium => Self::High,
+			Self::High => Self::Low,
+		}
+	}
+}
+
+/// The player's preferences
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+	pub display: DisplayMode,
+	pub antialiasing: Antialiasing,
+	pub vsync: bool,
+	pub lights: LightQuality,
+	/// The volume of all audio, from 0 to 1
+	pub volume: f32,
+	pub language: Language,
+	/// The parameters of the first maze
+	pub maze: MazeParams,
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Self {
+			display: DisplayMode::Borderless,
+			antialiasing: Antialiasing::Msaa4,
+			vsync: true,
+			lights: LightQuality::Medium,
+			volume: 1.0,
+			language: Language::detect(),
+			maze: MazeParams::default(),
+		}
+	}
+}
+
+impl Settings {
+	/// Load the saved settings, using the defaults for invalid or missing ones
+	///
+	/// Returns the problems found in the saved settings too, as this runs
+	/// before logging is set up
+	pub fn load() -> (Self, Vec<String>) {
+		let mut problems = Vec::new();
+
+		let Some(saved) = storage::load_config(FILE_NAME) else {
+			return (Self::default(), problems);
+		};
+
+		let mut settings = toml::from_str::<Self>(&saved).unwrap_or_else(|e| {
+			problems.push(format!("invalid saved settings, using the defaults: {e}"));
+			Self::default()
+		});
+
+		settings.volume = settings.volume.clamp(0.0, 1.0);
+		settings.antialiasing = settings.antialiasing.supported();
+
+		if !settings.maze.is_valid() {
+			problems.push("invalid saved maze parameters, using the defaults".to_string());
+			settings.maze = MazeParams::default();
+		}
+
+		(settings, problems)
+	}
+
+	pub const fn present_mode(self) -> PresentMode {
+		if self.vsync {
+			PresentMode::AutoVsync
+		} else {
+			PresentMode::AutoNoVsync
+		}
+	}
+}
+
+/// Save the [`Settings`] whenever they change
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn save(settings: Res<Settings>) {
+	if !settings.is_changed() || settings.is_added() {
+		return;
+	}
+
+	match toml::to_string_pretty(&*settings) {
+		Ok(saved) => storage::save_config(FILE_NAME, &saved),
+		Err(e) => warn!("could not serialize settings: {e}"),
+	}
+}
+
+/// Apply the [`Settings`] when they change, and the light quality to newly
+/// spawned lights
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn apply(
+	settings: Res<Settings>,
+	mut windows: Query<&mut Window, With<PrimaryWindow>>,
+	mut msaa: ResMut<Msaa>,
+	mut volume: ResMut<GlobalVolume>,
+	mut locale: ResMut<Locale>,
+	mut directional_shadow_map: ResMut<DirectionalLightShadowMap>,
+	mut point_shadow_map: ResMut<PointLightShadowMap>,
+	mut torches: Query<&mut PointLight, With<FlickerTimer>>,
+	mut suns: Query<&mut DirectionalLight>,
+) {
+	let shadows = settings.lights.shadows();
+
+	for mut torch in &mut torches {
+		if (settings.is_changed() || torch.is_added()) && torch.shadows_enabled != shadows {
+			torch.shadows_enabled = shadows;
+		}
+	}
+
+	for mut sun in &mut suns {
+		if (settings.is_changed() || sun.is_added()) && sun.shadows_enabled != shadows {
+			sun.shadows_enabled = shadows;
+		}
+	}
+
+	if !settings.is_changed() || settings.is_added() {
+		return;
+	}
+
+	if !cfg!(target_arch = "wasm32") {
+		for mut window in &mut windows {
+			window.mode = settings.display.window_mode();
+			window.present_mode = settings.present_mode();
+		}
+	}
+
+	msaa.set_if_neq(settings.antialiasing.msaa());
+	*volume = GlobalVolume::new(settings.volume);
+	directional_shadow_map.size = settings.lights.directional_shadow_map().size;
+	point_shadow_map.size = settings.lights.point_shadow_map().size;
+
+	if locale.language() != settings.language {
+		*locale = Locale::new(settings.language);
+	}
+}
@@ -0,0 +1,107 @@
+//! The state of the game, with gameplay systems only running while playing, so
+//! that the game (including its timers) is frozen in menus.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+	bindings::{Action, Actions},
+	ghost::CurrentRun,
+	maze::{MazeRegenerated, TileTextures},
+	multiplayer::{Multiplayer, Race},
+	touch::TouchControls,
+};
+
+/// How long to wait for the first maze's textures before showing the menu
+/// anyway (e.g. because the theme could not be loaded)
+const LOADING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The state of the game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States)]
+pub enum AppState {
+	/// The first maze's textures are being generated
+	#[default]
+	Loading,
+	/// The menu is shown after starting, before the first maze is played
+	Menu,
+	Playing,
+	/// The menu is open while playing
+	Paused,
+	/// The current maze was won, and the win screen is shown
+	Won,
+}
+
+/// Show the menu once the first maze's textures are generated, or after
+/// [`LOADING_TIMEOUT`] if they are not, so that the game can not get stuck
+/// loading
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn loaded(
+	textures: Res<TileTextures>,
+	time: Res<Time<Real>>,
+	mut next: ResMut<NextState<AppState>>,
+) {
+	if textures.is_ready() {
+		next.set(AppState::Menu);
+	} else if time.elapsed() >= LOADING_TIMEOUT {
+		warn!("the maze's textures are not ready after {LOADING_TIMEOUT:?}, showing the menu");
+		next.set(AppState::Menu);
+	}
+}
+
+/// Open the menu (pausing the game) or close it (resuming the game)
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn toggle_menu(
+	actions: Actions,
+	touch: Res<TouchControls>,
+	state: Res<State<AppState>>,
+	mut next: ResMut<NextState<AppState>>,
+) {
+	if !actions.just_pressed(Action::Menu) && !touch.menu {
+		return;
+	}
+
+	// The win screen has its own buttons for opening the menu or continuing
+	match state.get() {
+		AppState::Loading | AppState::Won => {}
+		AppState::Menu | AppState::Paused => next.set(AppState::Playing),
+		AppState::Playing => next.set(AppState::Paused),
+	}
+}
+
+/// Win once the main player finishes the current run, or in local multiplayer
+/// once any player wins the race
+///
+/// This only happens once per run, so that playing on after the win screen
+/// does not show it again
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn win(
+	run: Res<CurrentRun>,
+	race: Res<Race>,
+	multiplayer: Res<Multiplayer>,
+	mut next: ResMut<NextState<AppState>>,
+	mut was_won: Local<bool>,
+) {
+	let won = if multiplayer.is_enabled() {
+		race.winner.is_some()
+	} else {
+		run.0.as_ref().is_some_and(|run| run.finished)
+	};
+
+	if won && !*was_won {
+		next.set(AppState::Won);
+	}
+
+	*was_won = won;
+}
+
+/// Play the next maze once it is generated after winning
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn next_maze(
+	mut regenerated: EventReader<MazeRegenerated>,
+	mut next: ResMut<NextState<AppState>>,
+) {
+	if regenerated.read().last().is_some() {
+		next.set(AppState::Playing);
+	}
+}
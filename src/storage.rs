@@ -0,0 +1,89 @@
+//! Saving and loading small files (like settings), in the platform's config
+//! directory on native and in `localStorage` on wasm.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+/// Get the path of the config file called `name`, in the `maze` directory in
+/// the platform's config directory
+#[cfg(not(target_arch = "wasm32"))]
+fn config_path(name: &str) -> Option<PathBuf> {
+	Some(dirs::config_dir()?.join("maze").join(name))
+}
+
+/// Get the contents of the config file called `name`, if it exists
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_config(name: &str) -> Option<String> {
+	std::fs::read_to_string(config_path(name)?).ok()
+}
+
+/// Get the contents of the config file called `name`, if it exists
+#[cfg(target_arch = "wasm32")]
+pub fn load_config(name: &str) -> Option<String> {
+	web_sys::window()?
+		.local_storage()
+		.ok()??
+		.get_item(&format!("maze-{name}"))
+		.ok()?
+}
+
+/// Save `contents` to the config file called `name`, logging a warning on
+/// failure
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_config(name: &str, contents: &str) {
+	let Some(path) = config_path(name) else {
+		warn!("could not save {name}: there is no config directory");
+		return;
+	};
+
+	let saved = path
+		.parent()
+		.map_or(Ok(()), std::fs::create_dir_all)
+		.and_then(|()| std::fs::write(&path, contents));
+
+	if let Err(e) = saved {
+		warn!("could not save {}: {e}", path.display());
+	}
+}
+
+/// Save `contents` to the config file called `name`, logging a warning on
+/// failure
+#[cfg(target_arch = "wasm32")]
+pub fn save_config(name: &str, contents: &str) {
+	let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) else {
+		warn!("could not save {name}: local storage is not available");
+		return;
+	};
+
+	if let Err(e) = storage.set_item(&format!("maze-{name}"), contents) {
+		warn!("could not save {name}: {e:?}");
+	}
+}
+
+/// Remove the config file called `name`, if it exists
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove_config(name: &str) {
+	let Some(path) = config_path(name) else {
+		return;
+	};
+
+	match std::fs::remove_file(&path) {
+		Ok(()) => {}
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+		Err(e) => warn!("could not remove {}: {e}", path.display()),
+	}
+}
+
+/// Remove the config file called `name`, if it exists
+#[cfg(target_arch = "wasm32")]
+pub fn remove_config(name: &str) {
+	let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) else {
+		return;
+	};
+
+	if let Err(e) = storage.remove_item(&format!("maze-{name}")) {
+		warn!("could not remove {name}: {e:?}");
+	}
+}
@@ -0,0 +1,385 @@
+//! Themes (texture packs) for the maze and the player, loaded from `.theme.ron`
+//! manifests.
+
+use std::{
+	error::Error,
+	fmt::{Display, Formatter, Result as FmtResult},
+	ops::Range,
+	sync::Arc,
+};
+
+use bevy::{
+	asset::{
+		io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadState, ParseAssetPathError,
+		ReadAssetBytesError,
+	},
+	prelude::*,
+	utils::{BoxedFuture, HashSet},
+};
+use image::{load_from_memory, ImageError, RgbaImage};
+use serde::Deserialize;
+
+use crate::maze::MazeError;
+
+/// The size (in pixels) of a wall autotile sheet, which contains five 16x16
+/// sprites side by side
+pub const AUTOTILE_SHEET_SIZE: (u32, u32) = (5 * 16, 16);
+
+/// The themes which are always available, the first of which is the default
+const BUILTIN_THEMES: [&str; 2] = ["themes/cave.theme.ron", "themes/garden.theme.ron"];
+
+/// A visual theme for the maze, the player and the lighting
+///
+/// The player's sprite sheets must have the same layout as the default ones
+/// (a single column of 24x32 pixel frames)
+#[derive(Debug, Clone, Asset, TypePath)]
+pub struct Theme {
+	/// The name of the theme shown in the menu
+	pub name: String,
+	/// Images used for the walls of the maze
+	pub wall: Arc<[RgbaImage]>,
+	/// Autotile sheets used for the walls of the maze instead of `wall`, if
+	/// there are any
+	///
+	/// Each sheet contains (from left to right) an isolated wall, a vertical
+	/// wall, a horizontal wall, a wall with inner corners and a wall surrounded
+	/// by other walls. Every 8x8 quarter of a wall is taken from the matching
+	/// quarter of one of these sprites, depending on the neighbouring walls.
+	pub wall_autotile: Arc<[RgbaImage]>,
+	/// Images used for the floor of the maze
+	pub floor: Arc<[RgbaImage]>,
+	/// Images used for the area outside of the maze
+	pub outside: Arc<[RgbaImage]>,
+	/// The base color of the maze's tiles
+	pub tile_color: Color,
+	/// The emissive color of the maze's tiles
+	pub emissive: Color,
+	/// The range of perceptual roughness of the maze's tiles
+	pub roughness: Range<f32>,
+	/// The range of reflectance of the maze's tiles
+	pub reflectance: Range<f32>,
+	/// The color of the roof over the maze
+	pub roof: Color,
+	/// The color of the player's light
+	pub player_light: Color,
+	/// The color of the directional "sun" light
+	pub sun_light: Color,
+	/// The player's idle sprite sheet
+	pub player_idle: Handle<Image>,
+	/// The player's walking sprite sheet
+	pub player_walking: Handle<Image>,
+}
+
+/// The on-disk representation of a [`Theme`]
+///
+/// Image paths are relative to the manifest, colors are sRGB(A) tuples
+#[derive(Debug, Clone, Deserialize)]
+struct ThemeManifest {
+	name: String,
+	wall: Vec<String>,
+	#[serde(default)]
+	wall_autotile: Vec<String>,
+	floor: Vec<String>,
+	outside: Vec<String>,
+	tile_color: [f32; 4],
+	emissive: [f32; 4],
+	emissive_strength: f32,
+	roughness: (f32, f32),
+	reflectance: (f32, f32),
+	roof: [f32; 4],
+	player_light: [f32; 4],
+	sun_light: [f32; 4],
+	player_idle: String,
+	player_walking: String,
+}
+
+/// An error encountered while loading a [`Theme`]
+#[derive(Debug)]
+pub enum ThemeError {
+	/// The manifest could not be read
+	Io(std::io::Error),
+	/// The manifest is not valid RON or is missing fields
+	Manifest(ron::error::SpannedError),
+	/// A path in the manifest is invalid
+	Path(ParseAssetPathError),
+	/// An image referenced by the manifest could not be read
+	ImageRead(ReadAssetBytesError),
+	/// An image referenced by the manifest could not be decoded
+	ImageDecode(ImageError),
+	/// One of the image lists in the manifest is empty
+	NoImages(&'static str),
+	/// A wall autotile sheet does not have the size [`AUTOTILE_SHEET_SIZE`]
+	AutotileSize(u32, u32),
+}
+
+impl Display for ThemeError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+		match self {
+			Self::Io(e) => f.write_fmt(format_args!("could not read theme: {e}")),
+			Self::Manifest(e) => f.write_fmt(format_args!("invalid theme manifest: {e}")),
+			Self::Path(e) => f.write_fmt(format_args!("invalid path in theme: {e}")),
+			Self::ImageRead(e) => f.write_fmt(format_args!("could not read theme image: {e}")),
+			Self::ImageDecode(e) => f.write_fmt(format_args!("could not decode theme image: {e}")),
+			Self::NoImages(kind) => f.write_fmt(format_args!("theme has no {kind} images")),
+			Self::AutotileSize(w, h) => f.write_fmt(format_args!(
+				"autotile sheet is {w}x{h} pixels instead of {}x{}",
+				AUTOTILE_SHEET_SIZE.0, AUTOTILE_SHEET_SIZE.1
+			)),
+		}
+	}
+}
+
+impl Error for ThemeError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			Self::Io(e) => Some(e),
+			Self::Manifest(e) => Some(e),
+			Self::Path(e) => Some(e),
+			Self::ImageRead(e) => Some(e),
+			Self::ImageDecode(e) => Some(e),
+			Self::NoImages(_) | Self::AutotileSize(..) => None,
+		}
+	}
+}
+
+/// Loader for `.theme.ron` files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThemeLoader;
+
+impl ThemeLoader {
+	/// Read and decode all images at the given paths (relative to the manifest)
+	async fn load_images(
+		paths: &[String],
+		kind: &'static str,
+		load_context: &mut LoadContext<'_>,
+	) -> Result<Arc<[RgbaImage]>, ThemeError> {
+		if paths.is_empty() {
+			return Err(ThemeError::NoImages(kind));
+		}
+
+		let mut images = Vec::with_capacity(paths.len());
+
+		for path in paths {
+			let path = load_context
+				.asset_path()
+				.resolve_embed(path)
+				.map_err(ThemeError::Path)?;
+			let bytes = load_context
+				.read_asset_bytes(path)
+				.await
+				.map_err(ThemeError::ImageRead)?;
+			images.push(
+				load_from_memory(&bytes)
+					.map_err(ThemeError::ImageDecode)?
+					.into_rgba8(),
+			);
+		}
+
+		Ok(images.into())
+	}
+}
+
+impl AssetLoader for ThemeLoader {
+	type Asset = Theme;
+	type Error = ThemeError;
+	type Settings = ();
+
+	fn load<'a>(
+		&'a self,
+		reader: &'a mut Reader,
+		_settings: &'a (),
+		load_context: &'a mut LoadContext,
+	) -> BoxedFuture<'a, Result<Theme, ThemeError>> {
+		Box::pin(async move {
+			let mut bytes = Vec::new();
+			reader
+				.read_to_end(&mut bytes)
+				.await
+				.map_err(ThemeError::Io)?;
+			let manifest =
+				ron::de::from_bytes::<ThemeManifest>(&bytes).map_err(ThemeError::Manifest)?;
+
+			let wall = Self::load_images(&manifest.wall, "wall", load_context).await?;
+			let wall_autotile = if manifest.wall_autotile.is_empty() {
+				Arc::from([])
+			} else {
+				Self::load_images(&manifest.wall_autotile, "autotile", load_context).await?
+			};
+
+			if let Some(sheet) = wall_autotile
+				.iter()
+				.find(|sheet| sheet.dimensions() != AUTOTILE_SHEET_SIZE)
+			{
+				return Err(ThemeError::AutotileSize(sheet.width(), sheet.height()));
+			}
+
+			let floor = Self::load_images(&manifest.floor, "floor", load_context).await?;
+			let outside = Self::load_images(&manifest.outside, "outside", load_context).await?;
+
+			let player_idle = load_context.load(
+				load_context
+					.asset_path()
+					.resolve_embed(&manifest.player_idle)
+					.map_err(ThemeError::Path)?,
+			);
+			let player_walking = load_context.load(
+				load_context
+					.asset_path()
+					.resolve_embed(&manifest.player_walking)
+					.map_err(ThemeError::Path)?,
+			);
+
+			let color = |[r, g, b, a]: [f32; 4]| Color::rgba(r, g, b, a);
+
+			Ok(Theme {
+				name: manifest.name,
+				wall,
+				wall_autotile,
+				floor,
+				outside,
+				tile_color: color(manifest.tile_color),
+				emissive: color(manifest.emissive) * manifest.emissive_strength,
+				roughness: manifest.roughness.0..manifest.roughness.1,
+				reflectance: manifest.reflectance.0..manifest.reflectance.1,
+				roof: color(manifest.roof),
+				player_light: color(manifest.player_light),
+				sun_light: color(manifest.sun_light),
+				player_idle,
+				player_walking,
+			})
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["theme.ron"]
+	}
+}
+
+/// All available themes and the currently selected one
+#[derive(Debug, Clone, Resource)]
+pub struct Themes {
+	/// Handles to all available themes
+	pub themes: Vec<Handle<Theme>>,
+	/// The index of the selected theme in `themes`
+	pub selected: usize,
+}
+
+impl Themes {
+	/// Get the handle of the selected theme
+	pub fn current(&self) -> &Handle<Theme> {
+		&self.themes[self.selected]
+	}
+
+	/// Select the default theme (the first built-in one)
+	pub const fn select_default(&mut self) {
+		self.selected = 0;
+	}
+
+	/// Select the next theme, wrapping around to the first one
+	pub const fn select_next(&mut self) {
+		self.selected = (self.selected + 1) % self.themes.len();
+	}
+
+	/// Get the name of the selected theme, or its path if it is not loaded
+	pub fn current_name(&self, themes: &Assets<Theme>) -> String {
+		themes.get(self.current()).map_or_else(
+			|| {
+				self.current()
+					.path()
+					.map_or_else(String::new, ToString::to_string)
+			},
+			|t| t.name.clone(),
+		)
+	}
+}
+
+/// The selected theme has changed (or was loaded for the first time)
+#[derive(Debug, Clone, Copy, Event)]
+pub struct ThemeChanged(pub AssetId<Theme>);
+
+/// Find `.theme.ron` files in the `themes` directory in the working directory
+///
+/// User themes can not be listed on wasm, where only the built-in ones are
+/// available
+fn user_themes() -> Vec<std::path::PathBuf> {
+	if cfg!(target_arch = "wasm32") {
+		return Vec::new();
+	}
+
+	let Ok(dir) = std::fs::read_dir("themes") else {
+		return Vec::new();
+	};
+
+	dir.filter_map(Result::ok)
+		.map(|entry| entry.path())
+		.filter(|path| {
+			path.file_name()
+				.and_then(|n| n.to_str())
+				.is_some_and(|n| n.ends_with(".theme.ron"))
+		})
+		.filter_map(|path| path.canonicalize().ok())
+		.collect()
+}
+
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn initialize(mut commands: Commands, asset_server: Res<AssetServer>) {
+	let themes = BUILTIN_THEMES
+		.into_iter()
+		.map(|path| asset_server.load(path))
+		.chain(
+			user_themes()
+				.into_iter()
+				.map(|path| asset_server.load(path)),
+		)
+		.collect();
+
+	commands.insert_resource(Themes {
+		themes,
+		selected: 0,
+	});
+}
+
+/// Send a [`ThemeChanged`] event when the selected theme changes or finishes
+/// loading
+///
+/// If the selected theme fails to load, the error is reported and the default
+/// (embedded) theme is selected instead, or the next theme which did not fail
+/// if even the default one failed
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn select(
+	mut themes: ResMut<Themes>,
+	assets: Res<Assets<Theme>>,
+	asset_server: Res<AssetServer>,
+	mut asset_events: EventReader<AssetEvent<Theme>>,
+	mut events: EventWriter<ThemeChanged>,
+	mut errors: EventWriter<MazeError>,
+	mut applied: Local<Option<AssetId<Theme>>>,
+	mut failed: Local<HashSet<AssetId<Theme>>>,
+) {
+	let mut current = themes.current().id();
+
+	if asset_server.get_load_state(current) == Some(LoadState::Failed) && failed.insert(current) {
+		MazeError::Theme(themes.current_name(&assets)).report(&mut errors);
+		themes.select_default();
+
+		for _ in 1..themes.themes.len() {
+			if !failed.contains(&themes.current().id()) {
+				break;
+			}
+
+			themes.select_next();
+		}
+
+		current = themes.current().id();
+	}
+
+	let modified = asset_events
+		.read()
+		.any(|e| matches!(e, AssetEvent::Modified { id } if *id == current));
+
+	if (modified || *applied != Some(current)) && assets.contains(current) {
+		*applied = Some(current);
+		events.send(ThemeChanged(current));
+	}
+}
@@ -0,0 +1,308 @@
+//! On-screen touch controls (a virtual joystick and buttons), shown once touch
+//! input is detected.
+
+use bevy::{
+	prelude::*,
+	render::{
+		render_asset::RenderAssetUsages,
+		render_resource::{Extent3d, TextureDimension, TextureFormat},
+	},
+};
+
+use crate::{
+	locale::{self, Locale, Localized},
+	util::PlayerInput,
+};
+
+/// The size of the joystick's base as a percentage of the window's height
+const JOYSTICK_SIZE: f32 = 25.0;
+/// The size of the joystick's knob as a percentage of the base's size
+const KNOB_SIZE: f32 = 40.0;
+/// The size (in pixels) of the generated joystick textures
+const TEXTURE_SIZE: u32 = 64;
+/// Joystick offsets smaller than this are ignored
+const DEADZONE: f32 = 0.1;
+/// How far from the joystick's center (as a multiple of its radius) a touch
+/// can start to grab the joystick
+const GRAB_DISTANCE: f32 = 1.5;
+
+const BASE_COLOR: [u8; 4] = [0xff, 0xff, 0xff, 0x30];
+const KNOB_COLOR: [u8; 4] = [0xff, 0xff, 0xff, 0x90];
+const BUTTON_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+const BUTTON_PRESSED_COLOR: Color = Color::rgba(0.3, 0.3, 0.3, 0.7);
+
+/// The state of the touch controls
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct TouchControls {
+	/// Whether touch input was detected and the controls are shown
+	pub enabled: bool,
+	/// Whether the menu button was just pressed
+	pub menu: bool,
+	/// Whether the minimap button was just pressed
+	pub minimap: bool,
+	/// The id of the touch holding the joystick and the joystick's offset
+	/// (within the range from `-1.0` to `1.0` on each axis, with `y` up)
+	joystick: Option<(u64, Vec2)>,
+}
+
+impl TouchControls {
+	/// Whether the touch with the given `id` is holding the joystick (and
+	/// should not be used for anything else)
+	pub fn holds_joystick(&self, id: u64) -> bool {
+		self.joystick.is_some_and(|(joystick, _)| joystick == id)
+	}
+}
+
+/// Marker for the node containing all touch controls
+#[derive(Debug, Clone, Copy, Component)]
+pub struct TouchControlsRoot;
+
+/// Marker for the joystick's base
+#[derive(Debug, Clone, Copy, Component)]
+pub struct JoystickBase;
+
+/// Marker for the joystick's knob
+#[derive(Debug, Clone, Copy, Component)]
+pub struct JoystickKnob;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum TouchButton {
+	Menu,
+	Minimap,
+	Sprint,
+	Dash,
+}
+
+impl TouchButton {
+	/// The id of the button's message
+	const fn message(self) -> &'static str {
+		match self {
+			Self::Menu => "action-menu",
+			Self::Minimap => "action-overview",
+			Self::Sprint => "action-sprint",
+			Self::Dash => "action-dash",
+		}
+	}
+}
+
+/// Create a filled circle texture
+fn circle_image(color: [u8; 4]) -> Image {
+	#[allow(clippy::cast_precision_loss)]
+	let radius = TEXTURE_SIZE as f32 / 2.0;
+
+	let data = (0..TEXTURE_SIZE)
+		.flat_map(|y| (0..TEXTURE_SIZE).map(move |x| (x, y)))
+		.flat_map(|(x, y)| {
+			#[allow(clippy::cast_precision_loss)]
+			let pos = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+
+			if pos.distance(Vec2::splat(radius)) <= radius {
+				color
+			} else {
+				[0; 4]
+			}
+		})
+		.collect();
+
+	Image::new(
+		Extent3d {
+			width: TEXTURE_SIZE,
+			height: TEXTURE_SIZE,
+			..default()
+		},
+		TextureDimension::D2,
+		data,
+		TextureFormat::Rgba8UnormSrgb,
+		RenderAssetUsages::default(),
+	)
+}
+
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn initialize(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	locale: Res<Locale>,
+	mut images: ResMut<Assets<Image>>,
+) {
+	let base = images.add(circle_image(BASE_COLOR));
+	let knob = images.add(circle_image(KNOB_COLOR));
+
+	let text_style = TextStyle {
+		font: asset_server.load(locale.font()),
+		font_size: 32.0,
+		color: Color::WHITE,
+	};
+
+	commands
+		.spawn((TouchControlsRoot, NodeBundle {
+			style: Style {
+				position_type: PositionType::Absolute,
+				width: Val::Percent(100.0),
+				height: Val::Percent(100.0),
+				..default()
+			},
+			visibility: Visibility::Hidden,
+			..default()
+		}))
+		.with_children(|builder| {
+			builder
+				.spawn((
+					JoystickBase,
+					// So that touching the joystick is not used for tap-to-move
+					Interaction::default(),
+					ImageBundle {
+						style: Style {
+							position_type: PositionType::Absolute,
+							left: Val::Vh(4.0),
+							bottom: Val::Vh(8.0),
+							width: Val::Vh(JOYSTICK_SIZE),
+							height: Val::Vh(JOYSTICK_SIZE),
+							..default()
+						},
+						image: UiImage::new(base),
+						..default()
+					},
+				))
+				.with_children(|builder| {
+					builder.spawn((JoystickKnob, ImageBundle {
+						style: Style {
+							position_type: PositionType::Absolute,
+							left: Val::Percent(50.0 - KNOB_SIZE / 2.0),
+							top: Val::Percent(50.0 - KNOB_SIZE / 2.0),
+							width: Val::Percent(KNOB_SIZE),
+							height: Val::Percent(KNOB_SIZE),
+							..default()
+						},
+						image: UiImage::new(knob),
+						..default()
+					}));
+				});
+
+			builder
+				.spawn(NodeBundle {
+					style: Style {
+						position_type: PositionType::Absolute,
+						right: Val::Vh(2.0),
+						top: Val::Vh(20.0),
+						display: Display::Flex,
+						flex_direction: FlexDirection::Column,
+						row_gap: Val::Vh(2.0),
+						..default()
+					},
+					..default()
+				})
+				.with_children(|builder| {
+					for button in [
+						TouchButton::Menu,
+						TouchButton::Minimap,
+						TouchButton::Sprint,
+						TouchButton::Dash,
+					] {
+						builder
+							.spawn((button, ButtonBundle {
+								style: Style {
+									width: Val::Vh(14.0),
+									height: Val::Vh(8.0),
+									justify_content: JustifyContent::Center,
+									align_items: AlignItems::Center,
+									..default()
+								},
+								background_color: BackgroundColor(BUTTON_COLOR),
+								..default()
+							}))
+							.with_children(|builder| {
+								builder.spawn(locale::text(
+									&locale,
+									Localized::new(button.message()),
+									text_style.clone(),
+								));
+							});
+					}
+				});
+		});
+
+	commands.insert_resource(TouchControls::default());
+}
+
+/// Show the touch controls once touch input is detected, and feed the
+/// joystick and buttons into the [`PlayerInput`] and [`TouchControls`]
+///
+/// This must run after [`util::input`](crate::util::input), which overwrites
+/// the [`PlayerInput`]
+#[allow(clippy::type_complexity)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn update(
+	mut controls: ResMut<TouchControls>,
+	mut input: ResMut<PlayerInput>,
+	touches: Res<Touches>,
+	mut root: Query<&mut Visibility, With<TouchControlsRoot>>,
+	base: Query<(&Node, &GlobalTransform), With<JoystickBase>>,
+	mut knob: Query<&mut Style, With<JoystickKnob>>,
+	mut buttons: Query<(Ref<Interaction>, &TouchButton, &mut BackgroundColor)>,
+) {
+	controls.menu = false;
+	controls.minimap = false;
+
+	if !controls.enabled {
+		if touches.iter().next().is_none() {
+			return;
+		}
+
+		controls.enabled = true;
+
+		for mut visibility in &mut root {
+			*visibility = Visibility::Inherited;
+		}
+	}
+
+	if let Ok((node, transform)) = base.get_single() {
+		let center = transform.translation().truncate();
+		let radius = node.size().x / 2.0;
+
+		if controls.joystick.is_none() {
+			controls.joystick = touches
+				.iter_just_pressed()
+				.find(|t| t.position().distance(center) <= radius * GRAB_DISTANCE)
+				.map(|t| (t.id(), Vec2::ZERO));
+		}
+
+		if let Some((id, _)) = controls.joystick {
+			controls.joystick = touches.get_pressed(id).map(|touch| {
+				let offset = (touch.position() - center) / radius;
+				(id, Vec2::new(offset.x, -offset.y).clamp_length_max(1.0))
+			});
+		}
+	}
+
+	let offset = controls.joystick.map_or(Vec2::ZERO, |(_, offset)| offset);
+
+	for mut style in &mut knob {
+		style.left = Val::Percent(offset.x.mul_add(50.0, 50.0 - KNOB_SIZE / 2.0));
+		style.top = Val::Percent((-offset.y).mul_add(50.0, 50.0 - KNOB_SIZE / 2.0));
+	}
+
+	if offset.length() > DEADZONE {
+		input.up = (input.up + offset.y).clamp(-1.0, 1.0);
+		input.right = (input.right + offset.x).clamp(-1.0, 1.0);
+	}
+
+	for (interaction, button, mut color) in &mut buttons {
+		let pressed = *interaction == Interaction::Pressed;
+		let just_pressed = pressed && interaction.is_changed();
+
+		match button {
+			TouchButton::Menu => controls.menu |= just_pressed,
+			TouchButton::Minimap => controls.minimap |= just_pressed,
+			TouchButton::Sprint => input.sprint |= pressed,
+			TouchButton::Dash => input.dash |= just_pressed,
+		}
+
+		if interaction.is_changed() {
+			color.0 = if pressed {
+				BUTTON_PRESSED_COLOR
+			} else {
+				BUTTON_COLOR
+			};
+		}
+	}
+}
@@ -1,4 +1,4 @@
-use bevy::{app::AppExit, prelude::*};
+use bevy::{app::AppExit, prelude::*, ui::FocusPolicy};
 use bevy_simple_text_input::{
 	TextInputBundle, TextInputCursorPos, TextInputInactive, TextInputSettings, TextInputTextStyle,
 	TextInputValue,
@@ -6,19 +6,242 @@ use bevy_simple_text_input::{
 
 use crate::{
 	algorithms::{DirectionalBias, MazeParams},
-	maze::{RegenerateMaze, MAX_MAZE_SIZE, MIN_MAZE_SIZE},
+	bindings::{Action, InputMap, Rebinding},
+	crash::{self, LastCrash},
+	ghost::{BestRuns, CurrentRun, GhostSettings},
+	locale::{self, Locale, Localized},
+	maze::{
+		MazeError, MazeRegenerated, RegenerateMaze, TileTextures, MAX_MAZE_SIZE, MIN_MAZE_SIZE,
+	},
+	multiplayer::{Multiplayer, Race},
+	player::{Player, Stamina},
+	presets::{Profiles, PRESETS},
+	replay::ReplayState,
+	settings::Settings,
+	state::AppState,
+	theme::{Theme, Themes},
 };
 
 const ACTIVE_SELECTOR_COLOR: Color = Color::WHITE;
 const INACTIVE_SELECTOR_COLOR: Color = Color::BLACK;
+const STAMINA_COLOR: Color = Color::rgb(0.3, 0.8, 0.4);
+const STAMINA_EMPTY_COLOR: Color = Color::rgb(0.8, 0.3, 0.2);
+const CONTROLS_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.85);
+/// How much a gamepad's deadzone changes with each click of its buttons
+const DEADZONE_STEP: f32 = 0.05;
+/// How much the volume changes with each click of its buttons
+const VOLUME_STEP: f32 = 0.1;
+const FOCUS_COLOR: Color = Color::rgb(1.0, 0.85, 0.3);
+const INVALID_COLOR: Color = Color::rgb(0.9, 0.25, 0.2);
+const DISABLED_COLOR: Color = Color::GRAY;
+/// How long (in seconds) an error is shown for
+const ERROR_DURATION: f32 = 8.0;
+/// The directional biases in the order of their selectors
+const BIASES: [DirectionalBias; 5] = [
+	DirectionalBias::VeryHorizontal,
+	DirectionalBias::Horizontal,
+	DirectionalBias::None,
+	DirectionalBias::Vertical,
+	DirectionalBias::VeryVertical,
+];
 
 #[derive(Debug, Clone, Copy, Resource)]
 pub struct Ui(Option<Entity>);
 
+/// The panel shown next to the menu, if one is open
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct UiPanel(Option<(Panel, Entity)>);
+
+/// A panel shown next to the menu, of which only one is open at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+	Controls,
+	Settings,
+	Presets,
+}
+
+/// The menu element focused with the keyboard or a gamepad, if any
+#[derive(Debug, Clone, Copy, Default, Resource)]
+pub struct UiFocus(Option<Entity>);
+
+/// The maze parameters entered into the menu which are invalid, in the order of
+/// their inputs
+///
+/// Mazes can not be generated from the menu while any are invalid.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct InvalidParams(Vec<(UiInput, InvalidParam)>);
+
+/// The reason a maze parameter entered into the menu is invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidParam {
+	/// The text is not a number
+	NotANumber,
+	/// The width or height is outside of `MIN_MAZE_SIZE..=MAX_MAZE_SIZE`
+	Size,
+	/// There are more rooms than fit in the maze, which fits at most the given
+	/// number of rooms
+	TooManyRooms(u16),
+}
+
+impl InvalidParam {
+	fn text(self, locale: &Locale) -> String {
+		match self {
+			Self::NotANumber => locale.get("invalid-number"),
+			Self::Size => locale.format("invalid-size", &[
+				("min", &MIN_MAZE_SIZE.to_string()),
+				("max", &MAX_MAZE_SIZE.to_string()),
+			]),
+			Self::TooManyRooms(max) => locale.format("invalid-rooms", &[("max", &max.to_string())]),
+		}
+	}
+}
+
+/// A way of navigating the menu with the keyboard or a gamepad
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Navigation {
+	Previous,
+	Next,
+	Decrease,
+	Increase,
+	Activate,
+}
+
 #[derive(Debug, Clone, Copy, Component)]
 pub enum UiButton {
 	Generate,
 	Close,
+	Theme,
+	/// Open or close the controls panel
+	Controls,
+	/// Start rebinding the action
+	Rebind(Action),
+	/// Remove all bindings of the action
+	Unbind(Action),
+	/// Restore the default bindings and deadzones
+	ResetBindings,
+	/// Change the deadzone of the gamepad by the given amount
+	Deadzone(Gamepad, f32),
+	/// Show or hide the ghost of the best run
+	Ghost,
+	/// Change the number of local players
+	Players,
+	/// Open or close the settings panel
+	Settings,
+	/// Open the menu from the win screen
+	Menu,
+	/// Open or close the presets panel
+	Presets,
+}
+
+/// A button in the presets panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum UiPresetButton {
+	/// Use the preset's or profile's maze parameters
+	Select(MazeParams),
+	/// Remove the profile at the index
+	Remove(usize),
+	/// Save the current maze parameters as a profile with the entered name
+	Save,
+}
+
+/// Marker for the input of the name a profile is saved under
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UiProfileName;
+
+/// Marker for the text showing the name of the preset or profile with the
+/// current maze parameters
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UiPresetName;
+
+/// A setting shown in the settings panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+	Display,
+	Antialiasing,
+	Vsync,
+	Lights,
+	Volume,
+	Language,
+	/// The parameters of the first maze
+	Maze,
+}
+
+/// A button in the settings panel
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub enum UiSettingButton {
+	/// Change the setting to its next value
+	Next(Setting),
+	/// Change the volume by the given amount
+	Volume(f32),
+	/// Use the current maze parameters for the first maze
+	SaveMaze,
+}
+
+/// Marker for the text showing a setting's value
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UiSetting(Setting);
+
+/// Marker for the text showing an action's bindings
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UiBindings(Action);
+
+/// Marker for the text showing a gamepad's deadzone
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UiDeadzone(Gamepad);
+
+/// Marker for the text showing the selected theme's name
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UiThemeName;
+
+/// Marker for the filled part of the stamina bar
+#[derive(Debug, Clone, Copy, Component)]
+pub struct StaminaBar;
+
+/// Marker for the text shown while the maze's textures are being generated
+#[derive(Debug, Clone, Copy, Component)]
+pub struct LoadingIndicator;
+
+/// Marker for the text shown while a replay is being recorded or played back
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ReplayIndicator;
+
+/// Marker for the text showing the last error encountered while generating or
+/// showing the maze
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ErrorMessage;
+
+/// Marker for the text showing the time of the current and best run
+#[derive(Debug, Clone, Copy, Component)]
+pub struct RunTime;
+
+/// Marker for the text showing whether the ghost is shown
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UiGhost;
+
+/// Marker for the text showing the number of local players
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UiPlayers;
+
+/// Marker for the text showing the invalid maze parameters, or information
+/// about the maze which would be generated
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UiParamInfo;
+
+/// Marker for the root of the win screen
+#[derive(Debug, Clone, Copy, Component)]
+pub struct WinScreen;
+
+/// Marker for the root of the dialog offering the last crash report
+#[derive(Debug, Clone, Copy, Component)]
+pub struct CrashDialog;
+
+/// A button in the crash dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum UiCrashButton {
+	/// Copy the crash report to the clipboard
+	Copy,
+	/// Close the dialog and forget the crash report
+	Dismiss,
 }
 
 #[derive(Debug, Clone, Copy, Component)]
@@ -37,7 +260,7 @@ impl UiSelector {
 	}
 }
 
-#[derive(Debug, Clone, Copy, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
 pub enum UiInput {
 	Width,
 	Height,
@@ -45,13 +268,13 @@ pub enum UiInput {
 }
 
 impl UiInput {
-	fn text(self) -> String {
+	/// The id of the input's label message
+	const fn message(self) -> &'static str {
 		match self {
-			Self::Width => "Szerokosc",
-			Self::Height => "Wysokosc",
-			Self::Rooms => "Pokoje",
+			Self::Width => "menu-width",
+			Self::Height => "menu-height",
+			Self::Rooms => "menu-rooms",
 		}
-		.to_string()
 	}
 
 	fn get(self, params: MazeParams) -> String {
@@ -62,68 +285,330 @@ impl UiInput {
 		}
 		.to_string()
 	}
+
+	const fn set(self, params: &mut MazeParams, value: u16) {
+		match self {
+			Self::Width => params.width = value,
+			Self::Height => params.height = value,
+			Self::Rooms => params.rooms = value,
+		}
+	}
+
+	/// Parse and validate the `text` entered into this input, with the other
+	/// parameters being `params`
+	fn validate(self, text: &str, params: MazeParams) -> Result<u16, InvalidParam> {
+		let value = text
+			.trim()
+			.parse::<u16>()
+			.map_err(|_| InvalidParam::NotANumber)?;
+
+		match self {
+			Self::Width | Self::Height if !(MIN_MAZE_SIZE..=MAX_MAZE_SIZE).contains(&value) => {
+				Err(InvalidParam::Size)
+			}
+			Self::Rooms if value > params.max_rooms() => {
+				Err(InvalidParam::TooManyRooms(params.max_rooms()))
+			}
+			_ => Ok(value),
+		}
+	}
 }
 
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
-pub fn initialize(mut commands: Commands, asset_server: Res<AssetServer>, params: Res<MazeParams>) {
-	let ui = spawn(&mut commands, asset_server, *params);
-	commands.insert_resource(Ui(Some(ui)));
+pub fn initialize(mut commands: Commands, asset_server: Res<AssetServer>, locale: Res<Locale>) {
+	commands.spawn((LoadingIndicator, Localized::new("loading"), TextBundle {
+		style: Style {
+			position_type: PositionType::Absolute,
+			right: Val::Vh(2.0),
+			top: Val::Vh(2.0),
+			..default()
+		},
+		text: Text::from_section(locale.get("loading"), TextStyle {
+			font: asset_server.load(locale.font()),
+			font_size: 32.0,
+			color: Color::WHITE,
+		}),
+		visibility: Visibility::Hidden,
+		..default()
+	}));
+
+	commands.spawn((ReplayIndicator, TextBundle {
+		style: Style {
+			position_type: PositionType::Absolute,
+			right: Val::Vh(2.0),
+			top: Val::Vh(6.0),
+			..default()
+		},
+		text: Text::from_section("", TextStyle {
+			font: asset_server.load(locale.font()),
+			font_size: 32.0,
+			color: Color::WHITE,
+		}),
+		..default()
+	}));
+
+	commands.spawn((ErrorMessage, TextBundle {
+		style: Style {
+			position_type: PositionType::Absolute,
+			right: Val::Vh(2.0),
+			top: Val::Vh(10.0),
+			max_width: Val::Vw(60.0),
+			..default()
+		},
+		text: Text::from_section("", TextStyle {
+			font: asset_server.load(locale.font()),
+			font_size: 24.0,
+			color: INVALID_COLOR,
+		}),
+		visibility: Visibility::Hidden,
+		..default()
+	}));
+
+	commands.spawn((RunTime, TextBundle {
+		style: Style {
+			position_type: PositionType::Absolute,
+			left: Val::Vh(2.0),
+			top: Val::Vh(2.0),
+			..default()
+		},
+		text: Text::from_section("", TextStyle {
+			font: asset_server.load(locale.font()),
+			font_size: 32.0,
+			color: Color::WHITE,
+		}),
+		..default()
+	}));
+
+	commands
+		.spawn(NodeBundle {
+			style: Style {
+				position_type: PositionType::Absolute,
+				left: Val::Vh(2.0),
+				bottom: Val::Vh(2.0),
+				width: Val::Vh(30.0),
+				height: Val::Vh(2.0),
+				padding: UiRect::all(Val::Vh(0.3)),
+				..default()
+			},
+			background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+			..default()
+		})
+		.with_children(|builder| {
+			builder.spawn((StaminaBar, NodeBundle {
+				style: Style {
+					width: Val::Percent(100.0),
+					height: Val::Percent(100.0),
+					..default()
+				},
+				background_color: BackgroundColor(STAMINA_COLOR),
+				..default()
+			}));
+		});
+
+	commands.insert_resource(Ui(None));
+	commands.insert_resource(UiPanel::default());
+	commands.insert_resource(UiFocus::default());
+	commands.insert_resource(InvalidParams::default());
 }
 
+/// Open the menu when entering [`AppState::Menu`] or [`AppState::Paused`]
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
-pub fn open_close(
+pub fn open(
 	mut ui: ResMut<Ui>,
 	mut commands: Commands,
-	key_input: Res<ButtonInput<KeyCode>>,
-	gamepads: Res<Gamepads>,
-	pad_input: Res<ButtonInput<GamepadButton>>,
 	asset_server: Res<AssetServer>,
+	locale: Res<Locale>,
 	params: Res<MazeParams>,
 ) {
-	let mut just_pressed = false;
-
-	for gamepad in gamepads.iter() {
-		if pad_input.just_pressed(GamepadButton {
-			gamepad,
-			button_type: GamepadButtonType::Start,
-		}) {
-			just_pressed = true;
-		}
+	if ui.0.is_none() {
+		ui.0 = Some(spawn(&mut commands, &asset_server, &locale, *params));
 	}
+}
 
-	if key_input.any_just_pressed([KeyCode::Tab, KeyCode::Escape]) {
-		just_pressed = true;
-	}
+/// Close the menu and its panels when leaving [`AppState::Menu`] or
+/// [`AppState::Paused`]
+///
+/// Invalid parameters left in the menu's inputs are forgotten, since the maze
+/// parameters are only changed once they are valid.
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn close(
+	mut ui: ResMut<Ui>,
+	mut panel: ResMut<UiPanel>,
+	mut rebinding: ResMut<Rebinding>,
+	mut invalid: ResMut<InvalidParams>,
+	mut commands: Commands,
+) {
+	let panel = panel.0.take().map(|(_, e)| e);
 
-	if just_pressed {
-		if let Some(e) = ui.0 {
-			commands.entity(e).despawn_recursive();
-			ui.0 = None;
-		} else {
-			ui.0 = Some(spawn(&mut commands, asset_server, *params));
-		}
+	for e in [ui.0.take(), panel].into_iter().flatten() {
+		commands.entity(e).despawn_recursive();
 	}
+
+	rebinding.0 = None;
+	invalid.0.clear();
 }
 
-#[allow(clippy::type_complexity)]
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
 pub fn click(
 	mut interaction: Query<(&Interaction, &UiButton), (Changed<Interaction>, With<Button>)>,
 	mut app_exit_events: EventWriter<AppExit>,
 	mut events: EventWriter<RegenerateMaze>,
+	mut themes: ResMut<Themes>,
+	mut commands: Commands,
+	mut panel: ResMut<UiPanel>,
+	mut map: ResMut<InputMap>,
+	mut rebinding: ResMut<Rebinding>,
+	mut ghost: ResMut<GhostSettings>,
+	mut multiplayer: ResMut<Multiplayer>,
+	locale: Res<Locale>,
+	profiles: Res<Profiles>,
+	invalid: Res<InvalidParams>,
+	gamepads: Res<Gamepads>,
+	asset_server: Res<AssetServer>,
+	mut next_state: ResMut<NextState<AppState>>,
 ) {
 	for (interaction, button) in &mut interaction {
 		if *interaction == Interaction::Pressed {
-			match button {
+			match *button {
 				UiButton::Generate => {
-					events.send(RegenerateMaze);
+					if invalid.0.is_empty() {
+						events.send(RegenerateMaze::default());
+					}
 				}
 				UiButton::Close => {
 					if !cfg!(target_arch = "wasm32") {
 						app_exit_events.send(AppExit);
 					}
 				}
+				UiButton::Theme => themes.select_next(),
+				UiButton::Controls | UiButton::Settings | UiButton::Presets => {
+					let kind = match *button {
+						UiButton::Controls => Panel::Controls,
+						UiButton::Settings => Panel::Settings,
+						_ => Panel::Presets,
+					};
+
+					// The panels are shown in the same place, so the open one is
+					// closed first
+					let open = panel.0.take();
+
+					if let Some((_, e)) = open {
+						commands.entity(e).despawn_recursive();
+						rebinding.0 = None;
+					}
+
+					if open.map(|(open, _)| open) != Some(kind) {
+						let e = match kind {
+							Panel::Controls => {
+								spawn_controls(&mut commands, &asset_server, &locale, &gamepads)
+							}
+							Panel::Settings => {
+								spawn_settings(&mut commands, &asset_server, &locale)
+							}
+							Panel::Presets => {
+								spawn_presets(&mut commands, &asset_server, &locale, &profiles)
+							}
+						};
+
+						panel.0 = Some((kind, e));
+					}
+				}
+				UiButton::Menu => next_state.set(AppState::Paused),
+				UiButton::Rebind(action) => rebinding.0 = Some(action),
+				UiButton::Unbind(action) => map.clear(action),
+				UiButton::ResetBindings => *map = InputMap::default(),
+				UiButton::Deadzone(gamepad, step) => {
+					if let Some(name) = gamepads.name(gamepad) {
+						let deadzone = map.deadzone(Some(name)) + step;
+						map.set_deadzone(name, deadzone);
+					}
+				}
+				UiButton::Ghost => ghost.enabled = !ghost.enabled,
+				UiButton::Players => {
+					// Start a new race with the new players
+					multiplayer.select_next();
+					events.send(RegenerateMaze::default());
+				}
+			}
+		}
+	}
+}
+
+/// Use, save and remove presets and profiles with the buttons of the presets
+/// panel
+///
+/// The panel is spawned again when the profiles change, so that it lists them.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn click_presets(
+	interaction: Query<(&Interaction, &UiPresetButton), Changed<Interaction>>,
+	mut commands: Commands,
+	mut panel: ResMut<UiPanel>,
+	mut profiles: ResMut<Profiles>,
+	mut params: ResMut<MazeParams>,
+	name: Query<&TextInputValue, With<UiProfileName>>,
+	asset_server: Res<AssetServer>,
+	locale: Res<Locale>,
+) {
+	for (interaction, button) in &interaction {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		match *button {
+			UiPresetButton::Select(preset) => *params = preset,
+			UiPresetButton::Remove(index) => profiles.remove(index),
+			UiPresetButton::Save => {
+				let Some(name) = name
+					.iter()
+					.map(|name| name.0.trim())
+					.find(|n| !n.is_empty())
+				else {
+					continue;
+				};
+
+				profiles.insert(name.to_string(), *params);
+			}
+		}
+
+		if profiles.is_changed() {
+			if let Some((Panel::Presets, e)) = panel.0 {
+				commands.entity(e).despawn_recursive();
+				let e = spawn_presets(&mut commands, &asset_server, &locale, &profiles);
+				panel.0 = Some((Panel::Presets, e));
+			}
+		}
+	}
+}
+
+/// Change the settings with the buttons of the settings panel
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn click_settings(
+	interaction: Query<(&Interaction, &UiSettingButton), Changed<Interaction>>,
+	mut settings: ResMut<Settings>,
+	params: Res<MazeParams>,
+) {
+	for (interaction, button) in &interaction {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		match *button {
+			UiSettingButton::Next(Setting::Display) => settings.display = settings.display.next(),
+			UiSettingButton::Next(Setting::Antialiasing) => {
+				settings.antialiasing = settings.antialiasing.next();
+			}
+			UiSettingButton::Next(Setting::Vsync) => settings.vsync = !settings.vsync,
+			UiSettingButton::Next(Setting::Lights) => settings.lights = settings.lights.next(),
+			UiSettingButton::Next(Setting::Language) => {
+				settings.language = settings.language.next();
 			}
+			UiSettingButton::Next(Setting::Volume | Setting::Maze) => {}
+			UiSettingButton::Volume(step) => {
+				settings.volume = (settings.volume + step).clamp(0.0, 1.0);
+			}
+			UiSettingButton::SaveMaze => settings.maze = *params,
 		}
 	}
 }
@@ -166,177 +651,1656 @@ pub fn focus(
 	}
 }
 
-pub fn update(
-	mut input: Query<(&mut TextInputValue, &UiInput), Changed<TextInputValue>>,
-	mut maze_params: ResMut<MazeParams>,
+/// Navigate the menu and the controls panel with the arrow keys, Tab and the
+/// D-pad, activate the focused element with Enter or A, and change numbers
+/// and the directional bias with left/right
+///
+/// Elements are activated by pressing their [`Interaction`] for a frame, so
+/// that they behave the same as when clicked.
+#[allow(
+	clippy::type_complexity,
+	clippy::too_many_arguments,
+	clippy::too_many_lines
+)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn navigate(
+	keys: Res<ButtonInput<KeyCode>>,
+	buttons: Res<ButtonInput<GamepadButton>>,
+	gamepads: Res<Gamepads>,
+	ui: Res<Ui>,
+	panel: Res<UiPanel>,
+	win_screens: Query<Entity, With<WinScreen>>,
+	dialogs: Query<Entity, With<CrashDialog>>,
+	mut focus: ResMut<UiFocus>,
+	mut params: ResMut<MazeParams>,
+	focusables: Query<
+		(Entity, &GlobalTransform, Option<&UiSelector>, Has<UiInput>),
+		Or<(
+			With<UiButton>,
+			With<UiSettingButton>,
+			With<UiPresetButton>,
+			With<UiCrashButton>,
+			With<UiInput>,
+			With<UiProfileName>,
+			With<UiSelector>,
+		)>,
+	>,
+	parents: Query<&Parent>,
+	mut interactions: Query<&mut Interaction>,
+	mut inputs: Query<(
+		&mut TextInputValue,
+		&mut TextInputInactive,
+		&mut TextInputCursorPos,
+	)>,
+	mut pressed: Local<Option<Entity>>,
 ) {
-	for (mut value, input) in &mut input {
-		let current_value = value.0.parse::<u16>().unwrap_or_default();
-		value.0 = current_value.to_string();
-
-		match input {
-			UiInput::Width => maze_params.width = current_value.clamp(MIN_MAZE_SIZE, MAX_MAZE_SIZE),
-			UiInput::Height => {
-				maze_params.height = current_value.clamp(MIN_MAZE_SIZE, MAX_MAZE_SIZE);
-			}
-			UiInput::Rooms => maze_params.rooms = current_value,
+	if let Some(mut interaction) = pressed.take().and_then(|e| interactions.get_mut(e).ok()) {
+		if *interaction == Interaction::Pressed {
+			*interaction = Interaction::None;
 		}
 	}
-}
 
-#[allow(clippy::too_many_lines)]
-#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
-fn spawn(commands: &mut Commands, asset_server: Res<AssetServer>, params: MazeParams) -> Entity {
-	let menu = asset_server.load("maze/menu.png");
+	let win_screen = win_screens.iter().next();
+	let dialog = dialogs.iter().next();
 
-	let elem_style = |x, y| Style {
-		width: Val::Percent(50.0),
-		height: Val::Percent(80.0),
-		margin: UiRect::horizontal(Val::Percent(1.0)),
-		grid_column: GridPlacement::start(x),
-		grid_row: GridPlacement::start(y),
-		..default()
+	if ui.0.is_none() && win_screen.is_none() && dialog.is_none() {
+		return;
+	}
+
+	// While typing into a text input, the keys are only used for typing
+	let typing = focus
+		.0
+		.and_then(|e| inputs.get(e).ok())
+		.is_some_and(|(_, inactive, _)| !inactive.0);
+
+	let key = |key| !typing && keys.just_pressed(key);
+	let pad = |button_type| {
+		gamepads.iter().any(|gamepad| {
+			buttons.just_pressed(GamepadButton {
+				gamepad,
+				button_type,
+			})
+		})
 	};
+	let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
 
-	let text_style = TextStyle {
-		font: asset_server.load("fonts/pixel.ttf"),
-		font_size: 64.0,
-		color: Color::WHITE,
+	let navigation = if key(KeyCode::ArrowUp)
+		|| (shift && key(KeyCode::Tab))
+		|| pad(GamepadButtonType::DPadUp)
+	{
+		Navigation::Previous
+	} else if key(KeyCode::ArrowDown) || key(KeyCode::Tab) || pad(GamepadButtonType::DPadDown) {
+		Navigation::Next
+	} else if key(KeyCode::ArrowLeft) || pad(GamepadButtonType::DPadLeft) {
+		Navigation::Decrease
+	} else if key(KeyCode::ArrowRight) || pad(GamepadButtonType::DPadRight) {
+		Navigation::Increase
+	} else if keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter])
+		|| pad(GamepadButtonType::South)
+	{
+		Navigation::Activate
+	} else {
+		return;
 	};
 
-	commands
-		.spawn(ImageBundle {
-			style: Style {
-				position_type: PositionType::Absolute,
-				top: Val::ZERO,
-				left: Val::ZERO,
-				width: Val::Percent(50.0),
-				height: Val::Percent(100.0),
-				display: Display::Grid,
-				grid_template_columns: vec![GridTrack::percent(50.0); 2],
-				grid_template_rows: vec![GridTrack::percent(12.0); 8],
-				padding: UiRect::axes(Val::Percent(5.0), Val::Percent(5.0)),
-				align_items: AlignItems::Center,
-				justify_content: JustifyContent::SpaceEvenly,
-				..default()
-			},
-			image: UiImage {
-				texture: menu,
-				..default()
-			},
-			..default()
+	// The menu's (or win screen's) elements come first, then the open panel's,
+	// each in rows from the top. Only the selected bias can be focused, the
+	// others are selected with left/right. While the crash dialog is open, only
+	// its elements can be focused.
+	let mut elements = focusables
+		.iter()
+		.filter(|(_, _, selector, _)| selector.is_none_or(|s| s.0 == params.bias))
+		.filter_map(|(entity, transform, ..)| {
+			let root = parents.iter_ancestors(entity).last()?;
+			let panel = if dialog.is_some() {
+				(Some(root) == dialog).then_some(0)?
+			} else if Some(root) == ui.0 || Some(root) == win_screen {
+				0
+			} else if Some(root) == panel.0.map(|(_, e)| e) {
+				1
+			} else {
+				return None;
+			};
+
+			let pos = transform.translation().truncate().round();
+			Some((panel, pos, entity))
 		})
-		.with_children(|builder| {
-			builder.spawn(
-				TextBundle::from_section("Labirynt", text_style.clone())
-					.with_style(elem_style(1, 1)),
-			);
+		.collect::<Vec<_>>();
 
-			for (i, kind) in [UiInput::Width, UiInput::Height, UiInput::Rooms]
-				.into_iter()
-				.enumerate()
-			{
-				builder.spawn(TextBundle {
-					style: elem_style(1, 2 + i16::try_from(i).unwrap()),
-					text: Text::from_section(kind.text(), text_style.clone()),
-					..default()
-				});
+	elements.sort_by(|(a_panel, a, _), (b_panel, b, _)| {
+		a_panel
+			.cmp(b_panel)
+			.then(a.y.total_cmp(&b.y))
+			.then(a.x.total_cmp(&b.x))
+	});
 
-				builder.spawn((
-					NodeBundle {
-						style: elem_style(2, 2 + i16::try_from(i).unwrap()),
-						..default()
-					},
-					TextInputBundle {
-						text_style: TextInputTextStyle(text_style.clone()),
-						settings: TextInputSettings {
-							retain_on_submit: true,
-							..default()
-						},
-						value: TextInputValue(kind.get(params)),
-						inactive: TextInputInactive(true),
-						..default()
-					},
-					kind,
-				));
-			}
+	let len = elements.len();
+	let current = focus
+		.0
+		.and_then(|f| elements.iter().position(|&(_, _, e)| e == f));
 
-			builder.spawn(TextBundle {
-				style: elem_style(1, 5),
-				text: Text::from_section("Typ", text_style.clone()),
-				..default()
-			});
+	let Some(index) = current else {
+		// The first navigation only shows where the focus is
+		focus.0 = elements.first().map(|&(_, _, e)| e);
+		return;
+	};
 
-			builder
-				.spawn(NodeBundle {
-					style: Style {
-						display: Display::Flex,
-						flex_direction: FlexDirection::Row,
-						align_items: AlignItems::Center,
-						justify_content: JustifyContent::SpaceBetween,
-						..elem_style(2, 5)
-					},
-					..default()
-				})
-				.with_children(|builder| {
-					use DirectionalBias::{
-						Horizontal, None, Vertical, VeryHorizontal, VeryVertical,
-					};
+	let entity = elements[index].2;
+	let previous = elements[(index + len - 1) % len].2;
+	let next = elements[(index + 1) % len].2;
 
-					for bias in [VeryHorizontal, Horizontal, None, Vertical, VeryVertical] {
-						let selector = UiSelector(bias);
+	let target = match navigation {
+		Navigation::Previous => previous,
+		Navigation::Next => next,
+		Navigation::Decrease | Navigation::Increase => {
+			let increase = navigation == Navigation::Increase;
 
-						builder
-							.spawn((selector, ButtonBundle {
-								style: Style {
-									width: Val::Percent(20.0),
-									aspect_ratio: Some(1.0),
-									align_items: AlignItems::Center,
-									justify_content: JustifyContent::Center,
-									padding: UiRect::all(Val::Px(5.0)),
-									border: UiRect::all(Val::Px(5.0)),
-									..default()
-								},
-								background_color: BackgroundColor(Color::BLACK),
-								border_color: BorderColor(INACTIVE_SELECTOR_COLOR),
-								..default()
-							}))
-							.with_children(|parent| {
-								let mut style = text_style.clone();
-								style.font_size /= 2.0;
+			// Only the numbers are changed, not e.g. a profile's name
+			let number = focusables.get(entity).is_ok_and(|(.., number)| number);
+			let input = inputs.get_mut(entity).ok().filter(|_| number);
 
-								parent.spawn(TextBundle::from_section(selector.text(), style));
-							});
-					}
-				});
+			if let Some((mut value, _, mut cursor)) = input {
+				let current = value.0.parse::<u16>().unwrap_or_default();
+				let new = if increase {
+					current.saturating_add(1)
+				} else {
+					current.saturating_sub(1)
+				};
 
-			builder
-				.spawn((
-					ButtonBundle {
-						style: elem_style(1, 8),
-						background_color: BackgroundColor(Color::BLACK),
-						..default()
-					},
-					UiButton::Generate,
-				))
-				.with_children(|parent| {
-					parent.spawn(TextBundle::from_section("Generuj", text_style.clone()));
-				});
+				value.0 = new.to_string();
+				cursor.0 = value.0.len();
+				entity
+			} else if let Ok((_, _, Some(selector), _)) = focusables.get(entity) {
+				let i = BIASES.iter().position(|&b| b == selector.0).unwrap_or(0);
+				let i = if increase {
+					(i + 1).min(BIASES.len() - 1)
+				} else {
+					i.saturating_sub(1)
+				};
 
-			if !cfg!(target_arch = "wasm32") {
-				builder
-					.spawn((
+				params.bias = BIASES[i];
+
+				focusables
+					.iter()
+					.find(|(_, _, s, _)| s.is_some_and(|s| s.0 == BIASES[i]))
+					.map_or(entity, |(e, ..)| e)
+			} else if increase {
+				next
+			} else {
+				previous
+			}
+		}
+		Navigation::Activate => {
+			if typing {
+				if let Ok((_, mut inactive, _)) = inputs.get_mut(entity) {
+					inactive.0 = true;
+				}
+			} else if let Ok(mut interaction) = interactions.get_mut(entity) {
+				*interaction = Interaction::Pressed;
+				*pressed = Some(entity);
+			}
+
+			entity
+		}
+	};
+
+	if target != entity {
+		for (_, mut inactive, _) in &mut inputs {
+			if !inactive.0 {
+				inactive.0 = true;
+			}
+		}
+
+		focus.0 = Some(target);
+	}
+}
+
+/// Show a ring around the focused menu element
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn focus_ring(
+	mut commands: Commands,
+	focus: Res<UiFocus>,
+	outlined: Query<Entity, With<Outline>>,
+) {
+	if !focus.is_changed() {
+		return;
+	}
+
+	for entity in &outlined {
+		if Some(entity) != focus.0 {
+			commands.entity(entity).remove::<Outline>();
+		}
+	}
+
+	if let Some(entity) = focus.0 {
+		if let Some(mut entity) = commands.get_entity(entity) {
+			entity.try_insert(Outline::new(Val::Px(4.0), Val::Px(2.0), FOCUS_COLOR));
+		}
+	}
+}
+
+/// Show the name of the preset or profile with the current maze parameters on
+/// the presets button
+pub fn preset_name(
+	params: Res<MazeParams>,
+	profiles: Res<Profiles>,
+	locale: Res<Locale>,
+	mut text: Query<&mut Text, With<UiPresetName>>,
+) {
+	let preset = PRESETS
+		.iter()
+		.find(|preset| preset.params == *params)
+		.map(|preset| locale.get(preset.message));
+
+	let name = preset
+		.or_else(|| {
+			profiles
+				.0
+				.iter()
+				.find(|profile| profile.params == *params)
+				.map(|profile| profile.name.clone())
+		})
+		.unwrap_or_else(|| locale.get("presets-custom"));
+
+	for mut text in &mut text {
+		if text.sections[0].value != name {
+			text.sections[0].value.clone_from(&name);
+		}
+	}
+}
+
+pub fn theme_name(
+	themes: Res<Themes>,
+	assets: Res<Assets<Theme>>,
+	mut text: Query<&mut Text, With<UiThemeName>>,
+) {
+	let name = themes.current_name(&assets);
+
+	for mut text in &mut text {
+		if text.sections[0].value != name {
+			text.sections[0].value.clone_from(&name);
+		}
+	}
+}
+
+/// Show the current bindings and deadzones in the controls panel
+pub fn bindings(
+	map: Res<InputMap>,
+	rebinding: Res<Rebinding>,
+	gamepads: Res<Gamepads>,
+	mut bindings: Query<(&mut Text, &UiBindings), Without<UiDeadzone>>,
+	mut deadzones: Query<(&mut Text, &UiDeadzone), Without<UiBindings>>,
+) {
+	for (mut text, &UiBindings(action)) in &mut bindings {
+		let value = if rebinding.0 == Some(action) {
+			"...".to_string()
+		} else if map.bindings(action).is_empty() {
+			"-".to_string()
+		} else {
+			map.bindings(action)
+				.iter()
+				.map(ToString::to_string)
+				.collect::<Vec<_>>()
+				.join(", ")
+		};
+
+		if text.sections[0].value != value {
+			text.sections[0].value = value;
+		}
+	}
+
+	for (mut text, &UiDeadzone(gamepad)) in &mut deadzones {
+		let value = format!("{:.2}", map.deadzone(gamepads.name(gamepad)));
+
+		if text.sections[0].value != value {
+			text.sections[0].value = value;
+		}
+	}
+}
+
+/// Show the current settings in the settings panel
+pub fn settings(
+	settings: Res<Settings>,
+	locale: Res<Locale>,
+	mut texts: Query<(&mut Text, &UiSetting)>,
+) {
+	for (mut text, &UiSetting(setting)) in &mut texts {
+		let value = match setting {
+			Setting::Display => locale.get(settings.display.message()),
+			Setting::Antialiasing => locale.get(settings.antialiasing.message()),
+			Setting::Vsync if settings.vsync => locale.get("settings-on"),
+			Setting::Vsync => locale.get("settings-off"),
+			Setting::Lights => locale.get(settings.lights.message()),
+			Setting::Volume => format!("{:.0}%", settings.volume * 100.0),
+			Setting::Language => locale.get("language-name"),
+			Setting::Maze => locale.format("settings-maze-value", &[
+				("width", &settings.maze.width.to_string()),
+				("height", &settings.maze.height.to_string()),
+				("rooms", &settings.maze.rooms.to_string()),
+			]),
+		};
+
+		if text.sections[0].value != value {
+			text.sections[0].value = value;
+		}
+	}
+}
+
+/// Show the loading indicator while tile textures are being generated
+pub fn loading(
+	textures: Res<TileTextures>,
+	mut indicator: Query<&mut Visibility, With<LoadingIndicator>>,
+) {
+	let visibility = if textures.is_loading() {
+		Visibility::Inherited
+	} else {
+		Visibility::Hidden
+	};
+
+	for mut indicator in &mut indicator {
+		indicator.set_if_neq(visibility);
+	}
+}
+
+/// Show whether a replay is being recorded or played back
+pub fn replay(
+	state: Res<ReplayState>,
+	locale: Res<Locale>,
+	mut indicator: Query<&mut Text, With<ReplayIndicator>>,
+) {
+	if !state.is_changed() && !locale.is_changed() {
+		return;
+	}
+
+	let text = if state.is_recording() {
+		locale.get("replay-recording")
+	} else if state.is_playing() {
+		locale.get("replay-playing")
+	} else {
+		String::new()
+	};
+
+	for mut indicator in &mut indicator {
+		if indicator.sections[0].value != text {
+			indicator.sections[0].value.clone_from(&text);
+		}
+	}
+}
+
+/// Show the last [`MazeError`] for [`ERROR_DURATION`] seconds
+pub fn errors(
+	mut errors: EventReader<MazeError>,
+	time: Res<Time>,
+	locale: Res<Locale>,
+	mut timer: Local<Timer>,
+	mut message: Query<(&mut Text, &mut Visibility), With<ErrorMessage>>,
+) {
+	if let Some(error) = errors.read().last() {
+		let value = locale.format("maze-error", &[("error", &error.to_string())]);
+		*timer = Timer::from_seconds(ERROR_DURATION, TimerMode::Once);
+
+		for (mut text, mut visibility) in &mut message {
+			text.sections[0].value.clone_from(&value);
+			*visibility = Visibility::Inherited;
+		}
+	} else if timer.tick(time.delta()).just_finished() {
+		for (_, mut visibility) in &mut message {
+			*visibility = Visibility::Hidden;
+		}
+	}
+}
+
+/// Show the time of the current run, and of the best run through the maze
+pub fn run_time(
+	run: Res<CurrentRun>,
+	best: Res<BestRuns>,
+	locale: Res<Locale>,
+	mut text: Query<&mut Text, With<RunTime>>,
+) {
+	let Some(run) = &run.0 else {
+		return;
+	};
+
+	let elapsed = format!("{:.2}", run.elapsed.as_secs_f32());
+	let value = run.best_time(&best).map_or_else(
+		|| locale.format("run-time", &[("time", &elapsed)]),
+		|time| {
+			let best = format!("{:.2}", time.as_secs_f32());
+			locale.format("run-time-best", &[("time", &elapsed), ("best", &best)])
+		},
+	);
+
+	for mut text in &mut text {
+		if text.sections[0].value != value {
+			text.sections[0].value.clone_from(&value);
+		}
+	}
+}
+
+/// Show whether the ghost of the best run is shown on its menu button
+pub fn ghost(
+	settings: Res<GhostSettings>,
+	locale: Res<Locale>,
+	mut text: Query<&mut Text, With<UiGhost>>,
+) {
+	let value = if settings.enabled {
+		locale.get("menu-ghost-on")
+	} else {
+		locale.get("menu-ghost-off")
+	};
+
+	for mut text in &mut text {
+		if text.sections[0].value != value {
+			text.sections[0].value.clone_from(&value);
+		}
+	}
+}
+
+/// Show the number of local players on its menu button
+pub fn players(multiplayer: Res<Multiplayer>, mut text: Query<&mut Text, With<UiPlayers>>) {
+	let value = multiplayer.players.to_string();
+
+	for mut text in &mut text {
+		if text.sections[0].value != value {
+			text.sections[0].value.clone_from(&value);
+		}
+	}
+}
+
+/// Show the win screen when entering [`AppState::Won`], announcing the winner
+/// of a local multiplayer race, or the time of the finished run
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn open_win_screen(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	locale: Res<Locale>,
+	multiplayer: Res<Multiplayer>,
+	race: Res<Race>,
+	run: Res<CurrentRun>,
+	best: Res<BestRuns>,
+) {
+	let text_style = TextStyle {
+		font: asset_server.load(locale.font()),
+		font_size: 32.0,
+		color: Color::WHITE,
+	};
+
+	let mut title_style = text_style.clone();
+	title_style.font_size *= 2.0;
+
+	let title = match race.winner {
+		Some(winner) if multiplayer.is_enabled() => {
+			Localized::new("race-winner").with_arg("player", winner + 1)
+		}
+		_ => Localized::new("win-title"),
+	};
+
+	let time = run
+		.0
+		.as_ref()
+		.filter(|_| !multiplayer.is_enabled())
+		.map(|run| {
+			let elapsed = format!("{:.2}", run.elapsed.as_secs_f32());
+
+			match run.best_time(&best) {
+				Some(time) if time == run.elapsed => {
+					Localized::new("win-new-best").with_arg("time", elapsed)
+				}
+				Some(time) => Localized::new("run-time-best")
+					.with_arg("time", elapsed)
+					.with_arg("best", format!("{:.2}", time.as_secs_f32())),
+				None => Localized::new("run-time").with_arg("time", elapsed),
+			}
+		});
+
+	let button = ButtonBundle {
+		style: Style {
+			padding: UiRect::all(Val::Px(10.0)),
+			justify_content: JustifyContent::Center,
+			..default()
+		},
+		background_color: BackgroundColor(Color::BLACK),
+		..default()
+	};
+
+	commands
+		.spawn((
+			WinScreen,
+			NodeBundle {
+				style: Style {
+					position_type: PositionType::Absolute,
+					width: Val::Percent(100.0),
+					height: Val::Percent(100.0),
+					display: Display::Flex,
+					flex_direction: FlexDirection::Column,
+					align_items: AlignItems::Center,
+					justify_content: JustifyContent::Center,
+					row_gap: Val::Vh(3.0),
+					..default()
+				},
+				background_color: BackgroundColor(CONTROLS_BACKGROUND_COLOR),
+				..default()
+			},
+			// So that clicks on the win screen are not used for click-to-move
+			Interaction::default(),
+		))
+		.with_children(|builder| {
+			builder.spawn(locale::text(&locale, title, title_style));
+
+			if let Some(time) = time {
+				builder.spawn(locale::text(&locale, time, text_style.clone()));
+			}
+
+			builder
+				.spawn(NodeBundle {
+					style: Style {
+						display: Display::Flex,
+						flex_direction: FlexDirection::Row,
+						column_gap: Val::Vw(2.0),
+						..default()
+					},
+					..default()
+				})
+				.with_children(|builder| {
+					builder
+						.spawn((button.clone(), UiButton::Generate))
+						.with_children(|builder| {
+							builder.spawn(locale::text(
+								&locale,
+								Localized::new("win-next"),
+								text_style.clone(),
+							));
+						});
+
+					builder
+						.spawn((button, UiButton::Menu))
+						.with_children(|builder| {
+							builder.spawn(locale::text(
+								&locale,
+								Localized::new("win-menu"),
+								text_style,
+							));
+						});
+				});
+		});
+}
+
+/// Remove the win screen when leaving [`AppState::Won`]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn close_win_screen(mut commands: Commands, screens: Query<Entity, With<WinScreen>>) {
+	for screen in &screens {
+		commands.entity(screen).despawn_recursive();
+	}
+}
+
+/// Open the dialog offering the crash report of a previous launch over the
+/// menu, if there is one
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn open_crash_dialog(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	locale: Res<Locale>,
+	crash: Res<LastCrash>,
+	dialogs: Query<(), With<CrashDialog>>,
+) {
+	if crash.0.is_none() || !dialogs.is_empty() {
+		return;
+	}
+
+	let text_style = TextStyle {
+		font: asset_server.load(locale.font()),
+		font_size: 32.0,
+		color: Color::WHITE,
+	};
+
+	let mut title_style = text_style.clone();
+	title_style.font_size *= 1.5;
+
+	let button = ButtonBundle {
+		style: Style {
+			padding: UiRect::all(Val::Px(10.0)),
+			justify_content: JustifyContent::Center,
+			..default()
+		},
+		background_color: BackgroundColor(Color::BLACK),
+		..default()
+	};
+
+	commands
+		.spawn((
+			CrashDialog,
+			NodeBundle {
+				style: Style {
+					position_type: PositionType::Absolute,
+					width: Val::Percent(100.0),
+					height: Val::Percent(100.0),
+					display: Display::Flex,
+					flex_direction: FlexDirection::Column,
+					align_items: AlignItems::Center,
+					justify_content: JustifyContent::Center,
+					row_gap: Val::Vh(3.0),
+					padding: UiRect::all(Val::Percent(5.0)),
+					..default()
+				},
+				background_color: BackgroundColor(CONTROLS_BACKGROUND_COLOR),
+				// So that the menu below can not be clicked
+				focus_policy: FocusPolicy::Block,
+				z_index: ZIndex::Global(1),
+				..default()
+			},
+			Interaction::default(),
+		))
+		.with_children(|builder| {
+			builder.spawn(locale::text(
+				&locale,
+				Localized::new("crash-title"),
+				title_style,
+			));
+
+			builder.spawn(locale::text(
+				&locale,
+				Localized::new("crash-info"),
+				text_style.clone(),
+			));
+
+			builder
+				.spawn(NodeBundle {
+					style: Style {
+						display: Display::Flex,
+						flex_direction: FlexDirection::Row,
+						column_gap: Val::Vw(2.0),
+						..default()
+					},
+					..default()
+				})
+				.with_children(|builder| {
+					for (kind, message) in [
+						(UiCrashButton::Copy, "crash-copy"),
+						(UiCrashButton::Dismiss, "crash-dismiss"),
+					] {
+						builder
+							.spawn((button.clone(), kind))
+							.with_children(|builder| {
+								builder.spawn(locale::text(
+									&locale,
+									Localized::new(message),
+									text_style.clone(),
+								));
+							});
+					}
+				});
+		});
+}
+
+/// Copy or dismiss the crash report with the buttons of the crash dialog
+#[allow(clippy::type_complexity)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn click_crash(
+	interaction: Query<(&Interaction, &UiCrashButton, &Children), Changed<Interaction>>,
+	mut commands: Commands,
+	mut crash: ResMut<LastCrash>,
+	mut texts: Query<(&mut Text, &mut Localized)>,
+	dialogs: Query<Entity, With<CrashDialog>>,
+	locale: Res<Locale>,
+) {
+	for (interaction, button, children) in &interaction {
+		if *interaction != Interaction::Pressed {
+			continue;
+		}
+
+		match button {
+			UiCrashButton::Copy => {
+				let Some(report) = &crash.0 else {
+					continue;
+				};
+
+				let message = match crash::copy(report) {
+					Ok(()) => "crash-copied",
+					Err(e) => {
+						warn!("could not copy the crash report: {e}");
+						"crash-copy-failed"
+					}
+				};
+
+				for &child in children {
+					if let Ok((mut text, mut localized)) = texts.get_mut(child) {
+						*localized = Localized::new(message);
+						text.sections[0].value = locale.get(message);
+					}
+				}
+			}
+			UiCrashButton::Dismiss => {
+				crash.dismiss();
+
+				for dialog in &dialogs {
+					commands.entity(dialog).despawn_recursive();
+				}
+			}
+		}
+	}
+}
+
+/// Show the main player's remaining stamina in the stamina bar
+pub fn stamina(
+	stamina: Query<(&Player, &Stamina), Changed<Stamina>>,
+	mut bar: Query<(&mut Style, &mut BackgroundColor), With<StaminaBar>>,
+) {
+	let Some((_, stamina)) = stamina.iter().find(|(p, _)| p.is_main()) else {
+		return;
+	};
+
+	for (mut style, mut color) in &mut bar {
+		style.width = Val::Percent(stamina.current * 100.0);
+		color.0 = if stamina.current < 0.25 {
+			STAMINA_EMPTY_COLOR
+		} else {
+			STAMINA_COLOR
+		};
+	}
+}
+
+/// Validate the maze parameters entered into the menu, using them once they
+/// are all valid
+///
+/// The rooms are validated after the width and height, since the number of
+/// rooms which fit depends on them.
+pub fn update(
+	inputs: Query<(&TextInputValue, &UiInput)>,
+	changed: Query<(), (Changed<TextInputValue>, With<UiInput>)>,
+	mut maze_params: ResMut<MazeParams>,
+	mut invalid: ResMut<InvalidParams>,
+) {
+	if changed.is_empty() {
+		return;
+	}
+
+	let mut params = *maze_params;
+	invalid.0.clear();
+
+	for kind in [UiInput::Width, UiInput::Height, UiInput::Rooms] {
+		let Some((value, _)) = inputs.iter().find(|(_, input)| **input == kind) else {
+			continue;
+		};
+
+		match kind.validate(&value.0, params) {
+			Ok(value) => kind.set(&mut params, value),
+			Err(e) => invalid.0.push((kind, e)),
+		}
+	}
+
+	if invalid.0.is_empty() {
+		maze_params.set_if_neq(params);
+	}
+}
+
+/// Show maze parameters changed outside of the menu's inputs (e.g. by a preset
+/// or the JavaScript API) in them
+///
+/// Inputs which already contain the new value (possibly written differently)
+/// are left as they are, so that typing into them is not interrupted.
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn show_params(
+	params: Res<MazeParams>,
+	mut inputs: Query<(&mut TextInputValue, &mut TextInputCursorPos, &UiInput)>,
+) {
+	if !params.is_changed() {
+		return;
+	}
+
+	for (mut value, mut cursor, input) in &mut inputs {
+		let new = input.get(*params);
+
+		if value.0.trim().parse::<u16>().ok().map(|v| v.to_string()) != Some(new.clone()) {
+			value.0 = new;
+			cursor.0 = value.0.len();
+		}
+	}
+}
+
+/// Show the invalid maze parameters with a red border around their inputs and
+/// a message, disabling the generate button, or show the largest number of
+/// rooms and an estimate of the generation time if they are all valid
+///
+/// The generation time is estimated from the time per tile of the last
+/// generated maze.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+pub fn validation(
+	invalid: Res<InvalidParams>,
+	params: Res<MazeParams>,
+	locale: Res<Locale>,
+	mut regenerated: EventReader<MazeRegenerated>,
+	mut time_per_tile: Local<Option<f64>>,
+	mut inputs: Query<(&UiInput, &mut BorderColor)>,
+	buttons: Query<(&UiButton, &Children)>,
+	mut texts: Query<&mut Text, Without<UiParamInfo>>,
+	mut info: Query<&mut Text, With<UiParamInfo>>,
+) {
+	if let Some(event) = regenerated.read().last() {
+		let tiles = f64::from(event.params.width()) * f64::from(event.params.height());
+		*time_per_tile = Some(event.generation_time.as_secs_f64() / tiles);
+	}
+
+	for (input, mut border) in &mut inputs {
+		let color = if invalid.0.iter().any(|(kind, _)| kind == input) {
+			INVALID_COLOR
+		} else {
+			Color::NONE
+		};
+
+		if border.0 != color {
+			border.0 = color;
+		}
+	}
+
+	let generate_color = if invalid.0.is_empty() {
+		Color::WHITE
+	} else {
+		DISABLED_COLOR
+	};
+
+	let generate_texts = buttons
+		.iter()
+		.filter(|(button, _)| matches!(button, UiButton::Generate))
+		.flat_map(|(_, children)| children.iter());
+
+	for &child in generate_texts {
+		if let Ok(mut text) = texts.get_mut(child) {
+			if text.sections[0].style.color != generate_color {
+				text.sections[0].style.color = generate_color;
+			}
+		}
+	}
+
+	let (value, color) = if invalid.0.is_empty() {
+		let tiles = f64::from(params.width()) * f64::from(params.height());
+		let time = time_per_tile.map_or_else(
+			|| "?".to_string(),
+			|time| format!("{:.1}", time * tiles * 1000.0),
+		);
+
+		let value = locale.format("params-info", &[
+			("rooms", &params.max_rooms().to_string()),
+			("time", &time),
+		]);
+
+		(value, Color::WHITE)
+	} else {
+		let value = invalid
+			.0
+			.iter()
+			.map(|(kind, e)| format!("{}: {}", locale.get(kind.message()), e.text(&locale)))
+			.collect::<Vec<_>>()
+			.join("\n");
+
+		(value, INVALID_COLOR)
+	};
+
+	for mut text in &mut info {
+		if text.sections[0].value != value || text.sections[0].style.color != color {
+			text.sections[0].value.clone_from(&value);
+			text.sections[0].style.color = color;
+		}
+	}
+}
+
+#[allow(clippy::too_many_lines)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+fn spawn(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	locale: &Locale,
+	params: MazeParams,
+) -> Entity {
+	let menu = asset_server.load("maze/menu.png");
+
+	let elem_style = |x, y| Style {
+		width: Val::Percent(50.0),
+		height: Val::Percent(80.0),
+		margin: UiRect::horizontal(Val::Percent(1.0)),
+		grid_column: GridPlacement::start(x),
+		grid_row: GridPlacement::start(y),
+		..default()
+	};
+
+	let text_style = TextStyle {
+		font: asset_server.load(locale.font()),
+		font_size: 64.0,
+		color: Color::WHITE,
+	};
+
+	commands
+		.spawn((
+			ImageBundle {
+				style: Style {
+					position_type: PositionType::Absolute,
+					top: Val::ZERO,
+					left: Val::ZERO,
+					width: Val::Percent(50.0),
+					height: Val::Percent(100.0),
+					display: Display::Grid,
+					grid_template_columns: vec![GridTrack::percent(50.0); 2],
+					grid_template_rows: vec![GridTrack::percent(90.0 / 11.0); 11],
+					padding: UiRect::axes(Val::Percent(5.0), Val::Percent(5.0)),
+					align_items: AlignItems::Center,
+					justify_content: JustifyContent::SpaceEvenly,
+					..default()
+				},
+				image: UiImage {
+					texture: menu,
+					..default()
+				},
+				..default()
+			},
+			// So that clicks on the menu are not used for click-to-move
+			Interaction::default(),
+		))
+		.with_children(|builder| {
+			let (title, message) =
+				locale::text(locale, Localized::new("menu-title"), text_style.clone());
+			builder.spawn((title.with_style(elem_style(1, 1)), message));
+
+			builder
+				.spawn((
+					ButtonBundle {
+						style: elem_style(2, 1),
+						background_color: BackgroundColor(Color::BLACK),
+						..default()
+					},
+					UiButton::Settings,
+				))
+				.with_children(|parent| {
+					parent.spawn(locale::text(
+						locale,
+						Localized::new("menu-settings"),
+						text_style.clone(),
+					));
+				});
+
+			for (i, kind) in [UiInput::Width, UiInput::Height, UiInput::Rooms]
+				.into_iter()
+				.enumerate()
+			{
+				let (label, message) =
+					locale::text(locale, Localized::new(kind.message()), text_style.clone());
+				builder.spawn((
+					label.with_style(elem_style(1, 2 + i16::try_from(i).unwrap())),
+					message,
+				));
+
+				builder.spawn((
+					NodeBundle {
+						style: Style {
+							border: UiRect::all(Val::Px(3.0)),
+							..elem_style(2, 2 + i16::try_from(i).unwrap())
+						},
+						border_color: BorderColor(Color::NONE),
+						..default()
+					},
+					TextInputBundle {
+						text_style: TextInputTextStyle(text_style.clone()),
+						settings: TextInputSettings {
+							retain_on_submit: true,
+							..default()
+						},
+						value: TextInputValue(kind.get(params)),
+						inactive: TextInputInactive(true),
+						..default()
+					},
+					kind,
+				));
+			}
+
+			let (label, message) =
+				locale::text(locale, Localized::new("menu-bias"), text_style.clone());
+			builder.spawn((label.with_style(elem_style(1, 5)), message));
+
+			builder
+				.spawn(NodeBundle {
+					style: Style {
+						display: Display::Flex,
+						flex_direction: FlexDirection::Row,
+						align_items: AlignItems::Center,
+						justify_content: JustifyContent::SpaceBetween,
+						..elem_style(2, 5)
+					},
+					..default()
+				})
+				.with_children(|builder| {
+					for bias in BIASES {
+						let selector = UiSelector(bias);
+
+						builder
+							.spawn((selector, ButtonBundle {
+								style: Style {
+									width: Val::Percent(20.0),
+									aspect_ratio: Some(1.0),
+									align_items: AlignItems::Center,
+									justify_content: JustifyContent::Center,
+									padding: UiRect::all(Val::Px(5.0)),
+									border: UiRect::all(Val::Px(5.0)),
+									..default()
+								},
+								background_color: BackgroundColor(Color::BLACK),
+								border_color: BorderColor(INACTIVE_SELECTOR_COLOR),
+								..default()
+							}))
+							.with_children(|parent| {
+								let mut style = text_style.clone();
+								style.font_size /= 2.0;
+
+								parent.spawn(TextBundle::from_section(selector.text(), style));
+							});
+					}
+				});
+
+			let (label, message) =
+				locale::text(locale, Localized::new("menu-theme"), text_style.clone());
+			builder.spawn((label.with_style(elem_style(1, 6)), message));
+
+			builder
+				.spawn((
+					ButtonBundle {
+						style: elem_style(2, 6),
+						background_color: BackgroundColor(Color::BLACK),
+						..default()
+					},
+					UiButton::Theme,
+				))
+				.with_children(|parent| {
+					let mut style = text_style.clone();
+					style.font_size /= 2.0;
+
+					parent.spawn((UiThemeName, TextBundle::from_section("", style)));
+				});
+
+			builder
+				.spawn((
+					ButtonBundle {
+						style: elem_style(1, 7),
+						background_color: BackgroundColor(Color::BLACK),
+						..default()
+					},
+					UiButton::Controls,
+				))
+				.with_children(|parent| {
+					parent.spawn(locale::text(
+						locale,
+						Localized::new("menu-controls"),
+						text_style.clone(),
+					));
+				});
+
+			builder
+				.spawn((
+					ButtonBundle {
+						style: elem_style(2, 7),
+						background_color: BackgroundColor(Color::BLACK),
+						..default()
+					},
+					UiButton::Ghost,
+				))
+				.with_children(|parent| {
+					parent.spawn((UiGhost, TextBundle::from_section("", text_style.clone())));
+				});
+
+			let (label, message) =
+				locale::text(locale, Localized::new("menu-players"), text_style.clone());
+			builder.spawn((label.with_style(elem_style(1, 8)), message));
+
+			builder
+				.spawn((
+					ButtonBundle {
+						style: elem_style(2, 8),
+						background_color: BackgroundColor(Color::BLACK),
+						..default()
+					},
+					UiButton::Players,
+				))
+				.with_children(|parent| {
+					parent.spawn((UiPlayers, TextBundle::from_section("", text_style.clone())));
+				});
+
+			let (label, message) =
+				locale::text(locale, Localized::new("menu-presets"), text_style.clone());
+			builder.spawn((label.with_style(elem_style(1, 9)), message));
+
+			builder
+				.spawn((
+					ButtonBundle {
+						style: elem_style(2, 9),
+						background_color: BackgroundColor(Color::BLACK),
+						..default()
+					},
+					UiButton::Presets,
+				))
+				.with_children(|parent| {
+					let mut style = text_style.clone();
+					style.font_size /= 2.0;
+
+					parent.spawn((UiPresetName, TextBundle::from_section("", style)));
+				});
+
+			builder
+				.spawn((
+					ButtonBundle {
+						style: elem_style(1, 10),
+						background_color: BackgroundColor(Color::BLACK),
+						..default()
+					},
+					UiButton::Generate,
+				))
+				.with_children(|parent| {
+					parent.spawn(locale::text(
+						locale,
+						Localized::new("menu-generate"),
+						text_style.clone(),
+					));
+				});
+
+			let mut info_style = text_style.clone();
+			info_style.font_size /= 2.0;
+
+			builder.spawn((
+				UiParamInfo,
+				TextBundle::from_section("", info_style).with_style(Style {
+					grid_column: GridPlacement::span(2),
+					grid_row: GridPlacement::start(11),
+					..default()
+				}),
+			));
+
+			if !cfg!(target_arch = "wasm32") {
+				builder
+					.spawn((
 						ButtonBundle {
-							style: elem_style(2, 8),
+							style: elem_style(2, 10),
 							background_color: BackgroundColor(Color::BLACK),
 							..default()
 						},
 						UiButton::Close,
 					))
 					.with_children(|parent| {
-						parent.spawn(TextBundle::from_section("Zamknij", text_style));
+						parent.spawn(locale::text(
+							locale,
+							Localized::new("menu-close"),
+							text_style,
+						));
+					});
+			}
+		})
+		.id()
+}
+
+/// Spawn the controls panel, with the bindings of every action and the
+/// deadzones of the connected gamepads
+#[allow(clippy::too_many_lines)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+fn spawn_controls(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	locale: &Locale,
+	gamepads: &Gamepads,
+) -> Entity {
+	let text_style = TextStyle {
+		font: asset_server.load(locale.font()),
+		font_size: 24.0,
+		color: Color::WHITE,
+	};
+
+	let row_style = Style {
+		display: Display::Flex,
+		flex_direction: FlexDirection::Row,
+		align_items: AlignItems::Center,
+		column_gap: Val::Percent(2.0),
+		width: Val::Percent(100.0),
+		..default()
+	};
+
+	let button = |width: f32| ButtonBundle {
+		style: Style {
+			width: Val::Percent(width),
+			padding: UiRect::all(Val::Px(5.0)),
+			justify_content: JustifyContent::Center,
+			..default()
+		},
+		background_color: BackgroundColor(Color::BLACK),
+		..default()
+	};
+
+	commands
+		.spawn((
+			NodeBundle {
+				style: Style {
+					position_type: PositionType::Absolute,
+					top: Val::ZERO,
+					left: Val::Percent(50.0),
+					width: Val::Percent(50.0),
+					height: Val::Percent(100.0),
+					display: Display::Flex,
+					flex_direction: FlexDirection::Column,
+					row_gap: Val::Percent(1.0),
+					padding: UiRect::all(Val::Percent(3.0)),
+					..default()
+				},
+				background_color: BackgroundColor(CONTROLS_BACKGROUND_COLOR),
+				..default()
+			},
+			// So that clicks on the panel are not used for click-to-move
+			Interaction::default(),
+		))
+		.with_children(|builder| {
+			let mut title_style = text_style.clone();
+			title_style.font_size *= 2.0;
+
+			builder.spawn(locale::text(
+				locale,
+				Localized::new("controls-title"),
+				title_style,
+			));
+
+			for action in Action::ALL {
+				builder
+					.spawn(NodeBundle {
+						style: row_style.clone(),
+						..default()
+					})
+					.with_children(|builder| {
+						let (label, message) = locale::text(
+							locale,
+							Localized::new(action.message()),
+							text_style.clone(),
+						);
+						builder.spawn((
+							label.with_style(Style {
+								width: Val::Percent(20.0),
+								..default()
+							}),
+							message,
+						));
+
+						builder
+							.spawn((button(66.0), UiButton::Rebind(action)))
+							.with_children(|builder| {
+								builder.spawn((
+									UiBindings(action),
+									TextBundle::from_section("", text_style.clone()),
+								));
+							});
+
+						builder
+							.spawn((button(8.0), UiButton::Unbind(action)))
+							.with_children(|builder| {
+								builder.spawn(TextBundle::from_section("X", text_style.clone()));
+							});
 					});
 			}
+
+			for gamepad in gamepads.iter() {
+				let name = gamepads.name(gamepad).unwrap_or("Pad");
+
+				builder
+					.spawn(NodeBundle {
+						style: row_style.clone(),
+						..default()
+					})
+					.with_children(|builder| {
+						let (label, message) = locale::text(
+							locale,
+							Localized::new("controls-deadzone").with_arg("gamepad", name),
+							text_style.clone(),
+						);
+						builder.spawn((
+							label.with_style(Style {
+								width: Val::Percent(60.0),
+								..default()
+							}),
+							message,
+						));
+
+						builder
+							.spawn((button(8.0), UiButton::Deadzone(gamepad, -DEADZONE_STEP)))
+							.with_children(|builder| {
+								builder.spawn(TextBundle::from_section("-", text_style.clone()));
+							});
+
+						builder.spawn((
+							UiDeadzone(gamepad),
+							TextBundle::from_section("", text_style.clone()),
+						));
+
+						builder
+							.spawn((button(8.0), UiButton::Deadzone(gamepad, DEADZONE_STEP)))
+							.with_children(|builder| {
+								builder.spawn(TextBundle::from_section("+", text_style.clone()));
+							});
+					});
+			}
+
+			builder
+				.spawn((button(40.0), UiButton::ResetBindings))
+				.with_children(|builder| {
+					builder.spawn(locale::text(
+						locale,
+						Localized::new("controls-reset"),
+						text_style,
+					));
+				});
+		})
+		.id()
+}
+
+/// Spawn the settings panel, with a row for every setting
+///
+/// The window mode and vsync are controlled by the browser on wasm, so they
+/// are only shown on native.
+#[allow(clippy::too_many_lines)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+fn spawn_settings(commands: &mut Commands, asset_server: &AssetServer, locale: &Locale) -> Entity {
+	let text_style = TextStyle {
+		font: asset_server.load(locale.font()),
+		font_size: 24.0,
+		color: Color::WHITE,
+	};
+
+	let row_style = Style {
+		display: Display::Flex,
+		flex_direction: FlexDirection::Row,
+		align_items: AlignItems::Center,
+		column_gap: Val::Percent(2.0),
+		width: Val::Percent(100.0),
+		..default()
+	};
+
+	let label_style = Style {
+		width: Val::Percent(40.0),
+		..default()
+	};
+
+	let button = |width: f32| ButtonBundle {
+		style: Style {
+			width: Val::Percent(width),
+			padding: UiRect::all(Val::Px(5.0)),
+			justify_content: JustifyContent::Center,
+			..default()
+		},
+		background_color: BackgroundColor(Color::BLACK),
+		..default()
+	};
+
+	let rows = [
+		(Setting::Display, "settings-display"),
+		(Setting::Antialiasing, "settings-antialiasing"),
+		(Setting::Vsync, "settings-vsync"),
+		(Setting::Lights, "settings-lights"),
+		(Setting::Language, "settings-language"),
+	]
+	.into_iter()
+	.filter(|(setting, _)| {
+		!cfg!(target_arch = "wasm32") || !matches!(setting, Setting::Display | Setting::Vsync)
+	});
+
+	commands
+		.spawn((
+			NodeBundle {
+				style: Style {
+					position_type: PositionType::Absolute,
+					top: Val::ZERO,
+					left: Val::Percent(50.0),
+					width: Val::Percent(50.0),
+					height: Val::Percent(100.0),
+					display: Display::Flex,
+					flex_direction: FlexDirection::Column,
+					row_gap: Val::Percent(1.0),
+					padding: UiRect::all(Val::Percent(3.0)),
+					..default()
+				},
+				background_color: BackgroundColor(CONTROLS_BACKGROUND_COLOR),
+				..default()
+			},
+			// So that clicks on the panel are not used for click-to-move
+			Interaction::default(),
+		))
+		.with_children(|builder| {
+			let mut title_style = text_style.clone();
+			title_style.font_size *= 2.0;
+
+			builder.spawn(locale::text(
+				locale,
+				Localized::new("settings-title"),
+				title_style,
+			));
+
+			for (setting, message) in rows {
+				builder
+					.spawn(NodeBundle {
+						style: row_style.clone(),
+						..default()
+					})
+					.with_children(|builder| {
+						let (label, message) =
+							locale::text(locale, Localized::new(message), text_style.clone());
+						builder.spawn((label.with_style(label_style.clone()), message));
+
+						builder
+							.spawn((button(50.0), UiSettingButton::Next(setting)))
+							.with_children(|builder| {
+								builder.spawn((
+									UiSetting(setting),
+									TextBundle::from_section("", text_style.clone()),
+								));
+							});
+					});
+			}
+
+			builder
+				.spawn(NodeBundle {
+					style: row_style.clone(),
+					..default()
+				})
+				.with_children(|builder| {
+					let (label, message) = locale::text(
+						locale,
+						Localized::new("settings-volume"),
+						text_style.clone(),
+					);
+					builder.spawn((label.with_style(label_style.clone()), message));
+
+					builder
+						.spawn((button(8.0), UiSettingButton::Volume(-VOLUME_STEP)))
+						.with_children(|builder| {
+							builder.spawn(TextBundle::from_section("-", text_style.clone()));
+						});
+
+					builder.spawn((
+						UiSetting(Setting::Volume),
+						TextBundle::from_section("", text_style.clone()),
+					));
+
+					builder
+						.spawn((button(8.0), UiSettingButton::Volume(VOLUME_STEP)))
+						.with_children(|builder| {
+							builder.spawn(TextBundle::from_section("+", text_style.clone()));
+						});
+				});
+
+			builder
+				.spawn(NodeBundle {
+					style: row_style,
+					..default()
+				})
+				.with_children(|builder| {
+					let (label, message) =
+						locale::text(locale, Localized::new("settings-maze"), text_style.clone());
+					builder.spawn((label.with_style(label_style), message));
+
+					builder.spawn((
+						UiSetting(Setting::Maze),
+						TextBundle::from_section("", text_style.clone()),
+					));
+				});
+
+			builder
+				.spawn((button(40.0), UiSettingButton::SaveMaze))
+				.with_children(|builder| {
+					builder.spawn(locale::text(
+						locale,
+						Localized::new("settings-maze-save"),
+						text_style,
+					));
+				});
+		})
+		.id()
+}
+
+/// Spawn the presets panel, with the built-in presets, the saved profiles and
+/// an input for saving the current maze parameters as a new profile
+#[allow(clippy::too_many_lines)]
+#[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
+fn spawn_presets(
+	commands: &mut Commands,
+	asset_server: &AssetServer,
+	locale: &Locale,
+	profiles: &Profiles,
+) -> Entity {
+	let text_style = TextStyle {
+		font: asset_server.load(locale.font()),
+		font_size: 24.0,
+		color: Color::WHITE,
+	};
+
+	let row_style = Style {
+		display: Display::Flex,
+		flex_direction: FlexDirection::Row,
+		align_items: AlignItems::Center,
+		column_gap: Val::Percent(2.0),
+		width: Val::Percent(100.0),
+		..default()
+	};
+
+	let button = |width: f32| ButtonBundle {
+		style: Style {
+			width: Val::Percent(width),
+			padding: UiRect::all(Val::Px(5.0)),
+			justify_content: JustifyContent::Center,
+			..default()
+		},
+		background_color: BackgroundColor(Color::BLACK),
+		..default()
+	};
+
+	commands
+		.spawn((
+			NodeBundle {
+				style: Style {
+					position_type: PositionType::Absolute,
+					top: Val::ZERO,
+					left: Val::Percent(50.0),
+					width: Val::Percent(50.0),
+					height: Val::Percent(100.0),
+					display: Display::Flex,
+					flex_direction: FlexDirection::Column,
+					row_gap: Val::Percent(1.0),
+					padding: UiRect::all(Val::Percent(3.0)),
+					..default()
+				},
+				background_color: BackgroundColor(CONTROLS_BACKGROUND_COLOR),
+				..default()
+			},
+			// So that clicks on the panel are not used for click-to-move
+			Interaction::default(),
+		))
+		.with_children(|builder| {
+			let mut title_style = text_style.clone();
+			title_style.font_size *= 2.0;
+
+			builder.spawn(locale::text(
+				locale,
+				Localized::new("presets-title"),
+				title_style.clone(),
+			));
+
+			for preset in PRESETS {
+				builder
+					.spawn((button(66.0), UiPresetButton::Select(preset.params)))
+					.with_children(|builder| {
+						builder.spawn(locale::text(
+							locale,
+							Localized::new(preset.message),
+							text_style.clone(),
+						));
+					});
+			}
+
+			builder.spawn(locale::text(
+				locale,
+				Localized::new("presets-profiles"),
+				title_style,
+			));
+
+			for (i, profile) in profiles.0.iter().enumerate() {
+				builder
+					.spawn(NodeBundle {
+						style: row_style.clone(),
+						..default()
+					})
+					.with_children(|builder| {
+						builder
+							.spawn((button(66.0), UiPresetButton::Select(profile.params)))
+							.with_children(|builder| {
+								builder.spawn(TextBundle::from_section(
+									profile.name.clone(),
+									text_style.clone(),
+								));
+							});
+
+						builder
+							.spawn((button(8.0), UiPresetButton::Remove(i)))
+							.with_children(|builder| {
+								builder.spawn(TextBundle::from_section("X", text_style.clone()));
+							});
+					});
+			}
+
+			builder
+				.spawn(NodeBundle {
+					style: row_style,
+					..default()
+				})
+				.with_children(|builder| {
+					builder.spawn((
+						NodeBundle {
+							style: Style {
+								width: Val::Percent(50.0),
+								padding: UiRect::all(Val::Px(5.0)),
+								..default()
+							},
+							background_color: BackgroundColor(Color::BLACK),
+							..default()
+						},
+						TextInputBundle {
+							text_style: TextInputTextStyle(text_style.clone()),
+							settings: TextInputSettings {
+								retain_on_submit: true,
+								..default()
+							},
+							inactive: TextInputInactive(true),
+							..default()
+						},
+						UiProfileName,
+					));
+
+					builder
+						.spawn((button(40.0), UiPresetButton::Save))
+						.with_children(|builder| {
+							builder.spawn(locale::text(
+								locale,
+								Localized::new("presets-save"),
+								text_style,
+							));
+						});
+				});
 		})
 		.id()
 }
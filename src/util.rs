@@ -9,6 +9,7 @@ use std::{
 };
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 #[cfg(all(feature = "console_log", target_arch = "wasm32"))]
 use tracing_core::{subscriber::Interest, Level, Metadata};
 #[cfg(all(feature = "console_log", target_arch = "wasm32"))]
@@ -17,8 +18,10 @@ use tracing_subscriber::{
 	fmt::{format::Writer, time::FormatTime},
 	layer::{Context, Filter},
 };
-use turborand::rng::AtomicRng;
 pub use turborand::TurboRand;
+use turborand::{rng::AtomicRng, SeededCore};
+
+use crate::bindings::{Action, Actions};
 
 /// Random number generator resource
 #[derive(Debug, Resource, Deref, DerefMut)]
@@ -30,16 +33,34 @@ impl Rand {
 	pub fn new() -> Self {
 		Self(AtomicRng::new())
 	}
+
+	/// Create a new generator which always produces the same numbers for the
+	/// same `seed`
+	#[must_use]
+	pub fn with_seed(seed: u64) -> Self {
+		Self(AtomicRng::with_seed(seed))
+	}
 }
 
-/// Up/down/left/right movement input within the range from `-1.0` to `1.0`
+/// Up/down/left/right movement input within the range from `-1.0` to `1.0`,
+/// along with the sprint and dash inputs
+///
+/// Gamepad axes within their gamepad's deadzone are ignored, so without input
+/// both axes are exactly `0.0`
 ///
-/// If the input for either axis is within the deadzone, it is set to exactly
-/// `0.0`
-#[derive(Debug, Clone, Copy, Resource, Default)]
+/// The resource is the input of the main player, and every player has their
+/// own copy as a component
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Component, Default, Serialize, Deserialize)]
 pub struct PlayerInput {
 	pub up: f32,
 	pub right: f32,
+	/// Whether the sprint button is held
+	pub sprint: bool,
+	/// Whether the dash button was pressed since the player last moved (this
+	/// is reset by [`player::main_input`](crate::player::main_input) for the
+	/// resource and [`player::movement`](crate::player::movement) for the
+	/// components)
+	pub dash: bool,
 }
 
 impl PlayerInput {
@@ -51,13 +72,13 @@ impl PlayerInput {
 }
 
 impl From<PlayerInput> for Vec2 {
-	fn from(PlayerInput { up, right }: PlayerInput) -> Self {
+	fn from(PlayerInput { up, right, .. }: PlayerInput) -> Self {
 		Self { x: right, y: up }
 	}
 }
 
 impl From<PlayerInput> for Vec3 {
-	fn from(PlayerInput { up, right }: PlayerInput) -> Self {
+	fn from(PlayerInput { up, right, .. }: PlayerInput) -> Self {
 		Self {
 			x: right,
 			y: up,
@@ -66,8 +87,8 @@ impl From<PlayerInput> for Vec3 {
 	}
 }
 
-/// A system for processing up/down/left/right movement input, shared across
-/// games
+/// A system for processing up/down/left/right movement input through the
+/// [`InputMap`](crate::bindings::InputMap)
 ///
 /// # Usage
 ///
@@ -75,108 +96,15 @@ impl From<PlayerInput> for Vec3 {
 /// done automatically), then register this system, ideally before any
 /// movement/animation processing (e.g. in the `PreUpdate`) schedule
 #[cfg_attr(feature = "debug", tracing::instrument(skip_all))]
-pub fn input(
-	mut input: ResMut<PlayerInput>,
-	key_input: Res<ButtonInput<KeyCode>>,
-	gamepads: Res<Gamepads>,
-	pad_input: Res<ButtonInput<GamepadButton>>,
-	stick_input: Res<Axis<GamepadAxis>>,
-) {
-	const DEADZONE: f32 = 0.05;
-
-	let mut up = 0.0;
-	let mut right = 0.0;
-
-	// Keyboard WASD
-	if key_input.pressed(KeyCode::KeyW) {
-		up += 1.0;
-	}
-
-	if key_input.pressed(KeyCode::KeyS) {
-		up -= 1.0;
-	}
-
-	if key_input.pressed(KeyCode::KeyD) {
-		right += 1.0;
-	}
-
-	if key_input.pressed(KeyCode::KeyA) {
-		right -= 1.0;
-	}
-
-	// Keyboard arrow keys
-	if key_input.pressed(KeyCode::ArrowUp) {
-		up += 1.0;
-	}
-
-	if key_input.pressed(KeyCode::ArrowDown) {
-		up -= 1.0;
-	}
-
-	if key_input.pressed(KeyCode::ArrowRight) {
-		right += 1.0;
-	}
-
-	if key_input.pressed(KeyCode::ArrowLeft) {
-		right -= 1.0;
-	}
-
-	for gamepad in gamepads.iter() {
-		// Gamepad buttons
-		if pad_input.pressed(GamepadButton {
-			gamepad,
-			button_type: GamepadButtonType::DPadUp,
-		}) {
-			up += 1.0;
-		}
-
-		if pad_input.pressed(GamepadButton {
-			gamepad,
-			button_type: GamepadButtonType::DPadDown,
-		}) {
-			up -= 1.0;
-		}
-
-		if pad_input.pressed(GamepadButton {
-			gamepad,
-			button_type: GamepadButtonType::DPadRight,
-		}) {
-			right += 1.0;
-		}
-
-		if pad_input.pressed(GamepadButton {
-			gamepad,
-			button_type: GamepadButtonType::DPadLeft,
-		}) {
-			right -= 1.0;
-		}
-
-		// Gamepad stick
-		if let Some(i) = stick_input.get(GamepadAxis {
-			gamepad,
-			axis_type: GamepadAxisType::LeftStickY,
-		}) {
-			if i.abs() > DEADZONE {
-				up += i;
-			}
-		}
-
-		if let Some(i) = stick_input.get(GamepadAxis {
-			gamepad,
-			axis_type: GamepadAxisType::LeftStickX,
-		}) {
-			if i.abs() > DEADZONE {
-				right += i;
-			}
-		}
-	}
-
-	let up = if up.abs() > DEADZONE { up } else { 0.0 };
-	let right = if right.abs() > DEADZONE { right } else { 0.0 };
+pub fn input(mut input: ResMut<PlayerInput>, actions: Actions) {
+	let up = actions.value(Action::Up) - actions.value(Action::Down);
+	let right = actions.value(Action::Right) - actions.value(Action::Left);
 
 	*input = PlayerInput {
 		up: up.clamp(-1.0, 1.0),
 		right: right.clamp(-1.0, 1.0),
+		sprint: actions.pressed(Action::Sprint),
+		dash: input.dash || actions.just_pressed(Action::Dash),
 	}
 }
 